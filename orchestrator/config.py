@@ -59,6 +59,8 @@ class Config:
         "--gateway-retries", str(self.gateway_retries),
         "mine",
         "--threads", threads,
+        "--fleet-size", str(len(self.keypairs)),
+        "--fleet-index", str(i),
       ]
       if self.thread_pool:
         command.append("--thread-pool")
@@ -0,0 +1,11 @@
+from messages.message import Message, MessageAdditionalInfo
+
+class ClaimedMessage(Message):
+  amount: float
+  signature: str
+  
+  def valid_data(self) -> bool:
+    return self.msg_type == "claimed"
+  
+  def handle(self, info: MessageAdditionalInfo):
+    print(f"{info.worker_name} claimed {self.amount} ORE: {self.signature}")
\ No newline at end of file
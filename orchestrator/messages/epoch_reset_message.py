@@ -0,0 +1,10 @@
+from messages.message import Message, MessageAdditionalInfo
+
+class EpochResetMessage(Message):
+  signature: str
+  
+  def valid_data(self) -> bool:
+    return self.msg_type == "epoch_reset"
+  
+  def handle(self, info: MessageAdditionalInfo):
+    print(f"{info.worker_name} reset the epoch: {self.signature}")
\ No newline at end of file
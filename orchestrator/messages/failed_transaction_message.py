@@ -2,6 +2,7 @@ from messages.message import Message, MessageAdditionalInfo
 
 class FailedTransactionMessage(Message):
   error: str
+  cause: str = ""
   
   def valid_data(self) -> bool:
     return self.msg_type == "failed_transaction"
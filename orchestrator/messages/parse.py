@@ -3,12 +3,18 @@ import messages.message
 from messages.started_message import StartedMessage
 from messages.landed_mine_transaction_message import LandedMineTransactionMessage
 from messages.failed_transaction_message import FailedTransactionMessage
+from messages.claimed_message import ClaimedMessage
+from messages.epoch_reset_message import EpochResetMessage
+from messages.transaction_message import TransactionMessage
     
 def parse_message(data: bytes | str) -> messages.message.Message | None:
   all_messages = [
     StartedMessage, 
     LandedMineTransactionMessage,
-    FailedTransactionMessage
+    FailedTransactionMessage,
+    ClaimedMessage,
+    EpochResetMessage,
+    TransactionMessage
   ]
   
   for message in all_messages:
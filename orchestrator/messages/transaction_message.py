@@ -0,0 +1,17 @@
+from messages.message import Message, MessageAdditionalInfo
+
+class TransactionMessage(Message):
+  signature: str
+  error: str | None = None
+  
+  def valid_data(self) -> bool:
+    return self.msg_type in [
+      "transaction_expired",
+      "transaction_dropped",
+      "transaction_rpc_rejected",
+      "transaction_failed"
+    ]
+  
+  def handle(self, info: MessageAdditionalInfo):
+    pass
+    # print(f"{info.worker_name} {self.msg_type}: {self.signature}")
\ No newline at end of file
@@ -1,41 +1,61 @@
 use std::str::FromStr;
 
-use solana_client::nonblocking::rpc_client::RpcClient;
+use serde::Serialize;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::{commitment_config::CommitmentConfig, signature::Signer};
 
-use crate::Miner;
+use crate::{
+    output::{QueryError, QueryOutput, Table},
+    utils::format_ore_amount,
+    Miner,
+};
+
+/// The ORE balance of an address, in base units so JSON output stays exact.
+#[derive(Serialize)]
+pub struct BalanceOutput {
+    pub address: String,
+    pub token_account: String,
+    pub balance: u64,
+}
+
+impl QueryOutput for BalanceOutput {
+    fn plain(&self) -> String {
+        format!("{} ORE", format_ore_amount(self.balance))
+    }
+
+    fn table(&self) -> Table {
+        Table::new(vec!["ADDRESS", "TOKEN ACCOUNT", "ORE"]).row(vec![
+            self.address.clone(),
+            self.token_account.clone(),
+            format_ore_amount(self.balance),
+        ])
+    }
+}
 
 impl Miner {
-    pub async fn balance(&self, address: Option<String>) {
-        let signer = self.signer();
+    pub async fn balance(&self, address: Option<String>) -> Result<BalanceOutput, QueryError> {
         let address = if let Some(address) = address {
-            if let Ok(address) = Pubkey::from_str(&address) {
-                address
-            } else {
-                println!("Invalid address: {:?}", address);
-                return;
-            }
+            Pubkey::from_str(&address)
+                .map_err(|_| QueryError::InvalidInput(format!("Invalid address: {:?}", address)))?
         } else {
-            signer.pubkey()
+            self.signer().pubkey()
         };
-        let client =
-            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
         let token_account_address = spl_associated_token_account::get_associated_token_address(
             &address,
             &ore::MINT_ADDRESS,
         );
-        match client.get_token_account(&token_account_address).await {
-            Ok(token_account) => {
-                if let Some(token_account) = token_account {
-                    println!("{:} ORE", token_account.token_amount.ui_amount_string);
-                } else {
-                    println!("Account not found");
-                }
-            }
-            Err(err) => {
-                println!("{:?}", err);
-            }
-        }
+        let token_account = self.client
+            .get_token_account(&token_account_address)
+            .await?
+            .ok_or_else(|| {
+                QueryError::AccountNotFound(format!(
+                    "No ORE token account found for {}",
+                    address
+                ))
+            })?;
+        Ok(BalanceOutput {
+            address: address.to_string(),
+            token_account: token_account_address.to_string(),
+            balance: token_account.token_amount.amount.parse().unwrap_or_default(),
+        })
     }
 }
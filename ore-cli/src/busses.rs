@@ -1,29 +1,43 @@
-use ore::{state::Bus, utils::AccountDeserialize, BUS_ADDRESSES, TOKEN_DECIMALS};
-use solana_client::{client_error::Result, nonblocking::rpc_client::RpcClient};
-use solana_sdk::commitment_config::CommitmentConfig;
+use serde::Serialize;
 
-use crate::Miner;
+use crate::{
+    output::{QueryError, QueryOutput, Table},
+    utils::format_ore_amount,
+    Miner,
+};
 
-impl Miner {
-    pub async fn busses(&self) {
-        let client =
-            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
-        for address in BUS_ADDRESSES.iter() {
-            let data = client.get_account_data(address).await.unwrap();
-            match Bus::try_from_bytes(&data) {
-                Ok(bus) => {
-                    let rewards = (bus.rewards as f64) / 10f64.powf(TOKEN_DECIMALS as f64);
-                    println!("Bus {}: {:} ORE", bus.id, rewards);
-                }
-                Err(_) => {}
-            }
-        }
+/// The rewards left in a bus, in ORE base units.
+#[derive(Serialize)]
+pub struct BusOutput {
+    pub id: u64,
+    pub rewards: u64,
+}
+
+impl QueryOutput for Vec<BusOutput> {
+    fn plain(&self) -> String {
+        self.iter()
+            .map(|bus| format!("Bus {}: {} ORE", bus.id, format_ore_amount(bus.rewards)))
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 
-    pub async fn get_bus(&self, id: usize) -> Result<Bus> {
-        let client =
-            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
-        let data = client.get_account_data(&BUS_ADDRESSES[id]).await?;
-        Ok(*Bus::try_from_bytes(&data).unwrap())
+    fn table(&self) -> Table {
+        self.iter().fold(Table::new(vec!["BUS", "REWARDS ORE"]), |table, bus| {
+            table.row(vec![bus.id.to_string(), format_ore_amount(bus.rewards)])
+        })
+    }
+}
+
+impl Miner {
+    pub async fn busses(&self) -> Result<Vec<BusOutput>, QueryError> {
+        let snapshot = self.get_snapshot(None).await?;
+        Ok(snapshot
+            .busses
+            .iter()
+            .map(|bus| BusOutput {
+                id: bus.id,
+                rewards: bus.rewards,
+            })
+            .collect())
     }
 }
@@ -0,0 +1,115 @@
+use async_trait::async_trait;
+use solana_account_decoder::parse_token::UiTokenAccount;
+use solana_client::{
+    client_error::Result as ClientResult,
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
+    rpc_response::{Response as RpcResponse, RpcSimulateTransactionResult},
+};
+use solana_sdk::{
+    account::Account, commitment_config::CommitmentConfig, hash::Hash, pubkey::Pubkey,
+    signature::Signature, transaction::Transaction,
+};
+use solana_transaction_status::TransactionStatus;
+
+/// The RPC requests the miner makes, so they can be served by something other than a
+/// live node. Requests use the commitment the client was created with, except for
+/// blockhashes and block heights, which are always read at confirmed commitment.
+#[async_trait]
+pub trait ChainClient: Send + Sync {
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account>;
+
+    async fn get_account_data(&self, pubkey: &Pubkey) -> ClientResult<Vec<u8>>;
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> ClientResult<RpcResponse<Vec<Option<Account>>>>;
+
+    async fn get_token_account(&self, pubkey: &Pubkey) -> ClientResult<Option<UiTokenAccount>>;
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> ClientResult<u64>;
+
+    /// Returns a blockhash and the last block height at which it is valid.
+    async fn get_latest_blockhash(&self) -> ClientResult<(Hash, u64)>;
+
+    async fn get_block_height(&self) -> ClientResult<u64>;
+
+    async fn send_transaction(
+        &self,
+        transaction: &Transaction,
+        config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature>;
+
+    async fn simulate_transaction(
+        &self,
+        transaction: &Transaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> ClientResult<RpcResponse<RpcSimulateTransactionResult>>;
+
+    async fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> ClientResult<RpcResponse<Vec<Option<TransactionStatus>>>>;
+}
+
+#[async_trait]
+impl ChainClient for RpcClient {
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account> {
+        RpcClient::get_account(self, pubkey).await
+    }
+
+    async fn get_account_data(&self, pubkey: &Pubkey) -> ClientResult<Vec<u8>> {
+        RpcClient::get_account_data(self, pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> ClientResult<RpcResponse<Vec<Option<Account>>>> {
+        self.get_multiple_accounts_with_commitment(pubkeys, self.commitment())
+            .await
+    }
+
+    async fn get_token_account(&self, pubkey: &Pubkey) -> ClientResult<Option<UiTokenAccount>> {
+        RpcClient::get_token_account(self, pubkey).await
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> ClientResult<u64> {
+        RpcClient::get_balance(self, pubkey).await
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<(Hash, u64)> {
+        self.get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+    }
+
+    async fn get_block_height(&self) -> ClientResult<u64> {
+        self.get_block_height_with_commitment(CommitmentConfig::confirmed())
+            .await
+    }
+
+    async fn send_transaction(
+        &self,
+        transaction: &Transaction,
+        config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature> {
+        self.send_transaction_with_config(transaction, config).await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        transaction: &Transaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> ClientResult<RpcResponse<RpcSimulateTransactionResult>> {
+        self.simulate_transaction_with_config(transaction, config)
+            .await
+    }
+
+    async fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> ClientResult<RpcResponse<Vec<Option<TransactionStatus>>>> {
+        RpcClient::get_signature_statuses(self, signatures).await
+    }
+}
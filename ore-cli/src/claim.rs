@@ -1,21 +1,21 @@
 use std::str::FromStr;
 
-use ore::{self, state::Proof, utils::AccountDeserialize};
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_program::pubkey::Pubkey;
-use solana_sdk::{
-    commitment_config::CommitmentConfig, compute_budget::ComputeBudgetInstruction,
-    signature::Signer,
+use ore::{self, state::Proof, MINT_ADDRESS};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, signature::Signature};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
 };
 
 use crate::{
     cu_limits::{CU_LIMIT_ATA, CU_LIMIT_CLAIM},
-    utils::proof_pubkey,
+    events::{emit, ClaimedMessage, FailedTransactionMessage, TokenAccountRegisteredMessage},
+    metrics::time_rpc,
+    submit_error::SubmitError,
+    utils::{deserialize_account, format_ore_amount, proof_pubkey},
     Miner,
 };
 
-use serde::{Serialize, Deserialize};
-
 impl Miner {
     pub async fn register_token_account(&self) {
         let token_account = self.initialize_ata().await;
@@ -23,71 +23,182 @@ impl Miner {
         self.send_registered_message();
     }
 
-    pub async fn claim(&self, cluster: String, beneficiary: Option<String>, amount: Option<f64>) {
-        let signer = self.signer();
-        let pubkey = signer.pubkey();
-        let client = RpcClient::new_with_commitment(cluster, CommitmentConfig::confirmed());
-        let beneficiary = match beneficiary {
-            Some(beneficiary) => {
-                Pubkey::from_str(&beneficiary).expect("Failed to parse beneficiary address")
+    /// Claims `amount` of the signer's rewards, or all of them, returning the exit code.
+    /// They go to the token account `beneficiary`, or else to the one of `to_owner` or of
+    /// the signer, which the claim creates if it does not exist yet.
+    pub async fn claim(
+        &self,
+        beneficiary: Option<String>,
+        to_owner: Option<String>,
+        amount: Option<u64>,
+    ) -> i32 {
+        let pubkey = self.signer().pubkey();
+
+        // Check the requested amount against the rewards available on chain.
+        let proof_address = proof_pubkey(pubkey);
+        let claimable = match self.client.get_multiple_accounts(&[proof_address]).await {
+            Ok(response) => match response.value.into_iter().next().flatten() {
+                Some(proof_account) => match deserialize_account::<Proof>(&proof_account.data) {
+                    Some(proof) => proof.claimable_rewards,
+                    None => {
+                        eprintln!("Invalid proof account {}", proof_address);
+                        return 1;
+                    }
+                },
+                None => {
+                    eprintln!("No proof account for {}: register first", pubkey);
+                    return 1;
+                }
+            },
+            Err(err) => {
+                eprintln!("Error looking up claimable rewards: {}", err);
+                return 1;
             }
-            None => self.initialize_ata().await,
         };
+        let amount = amount.unwrap_or(claimable);
+        if amount == 0 {
+            eprintln!("No rewards to claim");
+            return 1;
+        }
+        if amount > claimable {
+            eprintln!(
+                "Cannot claim {} ORE: only {} ORE is claimable",
+                format_ore_amount(amount),
+                format_ore_amount(claimable)
+            );
+            return 1;
+        }
 
-        let amount = if let Some(amount) = amount {
-            (amount * 10f64.powf(ore::TOKEN_DECIMALS as f64)) as u64
-        } else {
-            match client.get_account(&proof_pubkey(pubkey)).await {
-                Ok(proof_account) => {
-                    let proof = Proof::try_from_bytes(&proof_account.data).unwrap();
-                    proof.claimable_rewards
+        // Rewards go to a token account, or to the one of an owner.
+        let (beneficiary, owner) = match (beneficiary, to_owner) {
+            (Some(beneficiary), _) => match Pubkey::from_str(&beneficiary) {
+                Ok(beneficiary) => (beneficiary, None),
+                Err(_) => {
+                    eprintln!("Invalid address: {:?}", beneficiary);
+                    return 2;
                 }
-                Err(err) => {
-                    println!("Error looking up claimable rewards: {:?}", err);
-                    return;
+            },
+            (None, Some(owner)) => match Pubkey::from_str(&owner) {
+                Ok(owner) => (get_associated_token_address(&owner, &MINT_ADDRESS), Some(owner)),
+                Err(_) => {
+                    eprintln!("Invalid address: {:?}", owner);
+                    return 2;
                 }
-            }
+            },
+            (None, None) => (get_associated_token_address(&pubkey, &MINT_ADDRESS), Some(pubkey)),
         };
-        let amountf = (amount as f64) / (10f64.powf(ore::TOKEN_DECIMALS as f64));
-        let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(CU_LIMIT_CLAIM);
-        let cu_price_ix = ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee);
-        let ix = ore::instruction::claim(pubkey, beneficiary, amount);
+
         println!("Submitting claim transaction...");
-        match self
-            .send_and_confirm_2(
-                &[cu_limit_ix, cu_price_ix, ix],
-                false,
-                self.confirm_retries,
-                self.confirm_interval,
-                self.gateway_retries,
-            )
-            .await
-        {
+        let result = match owner {
+            Some(owner) => self.try_claim_to_owner(owner, amount).await,
+            None => self.try_claim(beneficiary, amount).await,
+        };
+        match result {
             Ok(sig) => {
-                println!("Claimed {:} ORE to account {:}", amountf, beneficiary);
+                println!(
+                    "Claimed {} ORE to account {:}",
+                    format_ore_amount(amount),
+                    beneficiary
+                );
                 println!("{:?}", sig);
+                0
+            }
+            Err(SubmitError::Simulated) => {
+                println!("Claim simulated, not sent");
+                0
             }
             Err(err) => {
-                println!("Error: {:?}", err);
+                eprintln!("Failed to claim: {}", err);
+                1
             }
         }
     }
 
-    async fn initialize_ata(&self) -> Pubkey {
-        // Initialize client.
-        let signer = self.signer();
-        let client =
-            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+    /// Claims `amount` to the token account `beneficiary`, which has to exist.
+    pub async fn try_claim(&self, beneficiary: Pubkey, amount: u64) -> Result<Signature, SubmitError> {
+        let ix = ore::instruction::claim(self.signer().pubkey(), beneficiary, amount);
+        self.send_claim(vec![ix], CU_LIMIT_CLAIM).await
+    }
+
+    /// Claims `amount` to the ORE token account of `owner`, creating it in the same
+    /// transaction if it does not exist yet.
+    pub async fn try_claim_to_owner(&self, owner: Pubkey, amount: u64) -> Result<Signature, SubmitError> {
+        let pubkey = self.signer().pubkey();
+        let beneficiary = get_associated_token_address(&owner, &MINT_ADDRESS);
+        let ixs = vec![
+            create_associated_token_account_idempotent(&pubkey, &owner, &MINT_ADDRESS, &spl_token::id()),
+            ore::instruction::claim(pubkey, beneficiary, amount),
+        ];
+        self.send_claim(ixs, CU_LIMIT_ATA + CU_LIMIT_CLAIM).await
+    }
+
+    async fn send_claim(&self, ixs: Vec<Instruction>, cu_limit: u32) -> Result<Signature, SubmitError> {
+        let mut all_ixs = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(cu_limit),
+            ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee),
+        ];
+        all_ixs.extend(ixs);
+        self.send_and_confirm_2(
+            &all_ixs,
+            false,
+            self.confirm_retries,
+            self.confirm_interval,
+            self.gateway_retries,
+        )
+        .await
+    }
+
+    /// Claims rewards on behalf of `mine`, reporting the outcome as a message.
+    pub async fn auto_claim(&self, beneficiary: Pubkey, amount: u64) {
+        let amountf = (amount as f64) / (10f64.powf(ore::TOKEN_DECIMALS as f64));
+        println!("Auto-claiming {} ORE to account {}...", amountf, beneficiary);
+        match self.try_claim(beneficiary, amount).await {
+            Ok(sig) => self.send_claimed_message(amountf, sig),
+            Err(SubmitError::Simulated) => println!("Auto-claim simulated, not sent"),
+            Err(err) => {
+                eprintln!("Auto-claim failed: {}", err);
+                emit(&FailedTransactionMessage::new(&err));
+            }
+        }
+    }
 
+    pub async fn initialize_ata(&self) -> Pubkey {
+        let token_account_pubkey = spl_associated_token_account::get_associated_token_address(
+            &self.signer().pubkey(),
+            &ore::MINT_ADDRESS,
+        );
+        match self.try_initialize_ata().await {
+            Ok(true) => println!("Created token account {:?}", token_account_pubkey),
+            Ok(false) => {}
+            Err(e) => println!("Transaction failed: {:?}", e),
+        }
+
+        // Return token account address
+        token_account_pubkey
+    }
+
+    /// Creates the signer's ORE token account, returning whether it had to be created.
+    pub async fn try_initialize_ata(&self) -> Result<bool, SubmitError> {
+        self.try_initialize_ata_for(self.signer().pubkey()).await
+    }
+
+    /// Creates the ORE token account of `owner`, paid for by the signer.
+    pub async fn try_initialize_ata_for(&self, owner: Pubkey) -> Result<bool, SubmitError> {
+        let signer = self.signer();
         // Build instructions.
         let token_account_pubkey = spl_associated_token_account::get_associated_token_address(
-            &signer.pubkey(),
+            &owner,
             &ore::MINT_ADDRESS,
         );
 
         // Check if ata already exists
-        if let Ok(Some(_ata)) = client.get_token_account(&token_account_pubkey).await {
-            return token_account_pubkey;
+        if let Ok(Some(_ata)) = time_rpc(
+            "getTokenAccount",
+            self.client.get_token_account(&token_account_pubkey),
+        )
+        .await
+        {
+            return Ok(false);
         }
 
         // Sign and send transaction.
@@ -95,46 +206,115 @@ impl Miner {
         let cu_price_ix = ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee);
         let ix = spl_associated_token_account::instruction::create_associated_token_account(
             &signer.pubkey(),
-            &signer.pubkey(),
+            &owner,
             &ore::MINT_ADDRESS,
             &spl_token::id(),
         );
         println!("Creating token account {}...", token_account_pubkey);
-        match self
-            .send_and_confirm_2(
-                &[cu_limit_ix, cu_price_ix, ix], 
-                false,
-                self.confirm_retries,
-                self.confirm_interval,
-                self.gateway_retries,
-            )
-            .await
-        {
-            Ok(_sig) => println!("Created token account {:?}", token_account_pubkey),
-            Err(e) => println!("Transaction failed: {:?}", e),
-        }
-
-        // Return token account address
-        token_account_pubkey
+        self.send_and_confirm_2(
+            &[cu_limit_ix, cu_price_ix, ix], 
+            false,
+            self.confirm_retries,
+            self.confirm_interval,
+            self.gateway_retries,
+        )
+        .await?;
+        Ok(true)
     }
 
     fn send_registered_message(&self) {
-        let message = TokenAccountRegisteredMessage::new();
-        let json = serde_json::to_string(&message).unwrap();
-        println!("{}", json);
+        emit(&TokenAccountRegisteredMessage::new());
     }
-}
 
-#[derive(Serialize, Deserialize)]
-struct TokenAccountRegisteredMessage {
-    msg_type: String
+    fn send_claimed_message(&self, amount: f64, signature: Signature) {
+        emit(&ClaimedMessage::new(amount, signature));
+    }
 }
 
-impl TokenAccountRegisteredMessage {
-    
-    fn new() -> Self {
-        Self {
-            msg_type: "token_account_registered".to_string()
-        }
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+    use super::*;
+    use crate::mock_client::{MockChainClient, MockOutcome};
+
+    // The amount of the ORE claim instruction in `tx`.
+    fn claimed_amount(tx: &solana_sdk::transaction::Transaction) -> u64 {
+        let ix = tx.message.instructions.last().unwrap();
+        u64::from_le_bytes(ix.data[1..9].try_into().unwrap())
+    }
+
+    #[tokio::test]
+    async fn claims_all_rewards_by_default() {
+        let (client, miner) = MockChainClient::registered(5_000);
+        assert_eq!(miner.claim(None, None, None).await, 0);
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(claimed_amount(&sent[0]), 5_000);
+    }
+
+    #[tokio::test]
+    async fn claims_requested_amount() {
+        let (client, miner) = MockChainClient::registered(5_000);
+        assert_eq!(miner.claim(None, None, Some(2_000)).await, 0);
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(claimed_amount(&sent[0]), 2_000);
+    }
+
+    #[tokio::test]
+    async fn refuses_more_than_claimable() {
+        let (client, miner) = MockChainClient::registered(5_000);
+        assert_eq!(miner.claim(None, None, Some(5_001)).await, 1);
+        assert!(client.sent_transactions().is_empty());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn refuses_missing_or_invalid_proofs() {
+        let (client, miner) = MockChainClient::funded();
+        assert_eq!(miner.claim(None, None, None).await, 1);
+        client.set_account(
+            proof_pubkey(miner.signer().pubkey()),
+            solana_sdk::account::Account {
+                lamports: 1_000_000,
+                owner: ore::ID,
+                ..Default::default()
+            },
+        );
+        assert_eq!(miner.claim(None, None, None).await, 1);
+        assert!(client.sent_transactions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn creates_token_account_of_owner() {
+        let (client, miner) = MockChainClient::registered(5_000);
+        let owner = Pubkey::new_unique();
+        assert_eq!(miner.claim(None, Some(owner.to_string()), None).await, 0);
+        // The token account is created in the claim transaction, so neither lands alone.
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 1);
+        let program_ids: Vec<Pubkey> = sent[0]
+            .message
+            .instructions
+            .iter()
+            .map(|ix| sent[0].message.account_keys[ix.program_id_index as usize])
+            .collect();
+        assert_eq!(program_ids[2..], [spl_associated_token_account::id(), ore::ID]);
+        let beneficiary = get_associated_token_address(&owner, &MINT_ADDRESS);
+        assert!(sent[0].message.account_keys.contains(&beneficiary));
+    }
+
+    #[tokio::test]
+    async fn reports_rejected_claims() {
+        let (client, miner) = MockChainClient::registered(5_000);
+        client.push_outcome(MockOutcome::Fail(TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(u32::from(ore::error::OreError::ClaimTooLarge)),
+        )));
+        let beneficiary = Pubkey::new_unique();
+        assert!(matches!(
+            miner.try_claim(beneficiary, 5_000).await,
+            Err(SubmitError::Program(ore::error::OreError::ClaimTooLarge))
+        ));
+    }
+}
@@ -1,6 +1,10 @@
+// Default compute unit limits. With `--simulate`, measured limits replace them.
 pub const CU_LIMIT_REGISTER: u32 = 20_000;
 pub const CU_LIMIT_CLAIM: u32 = 50_000;
-// pub const CU_LIMIT_TRANSFER: u32 = 30_000; // TODO
+pub const CU_LIMIT_TRANSFER: u32 = 10_000;
 pub const CU_LIMIT_ATA: u32 = 50_000;
 pub const CU_LIMIT_RESET: u32 = 12_200;
-pub const CU_LIMIT_MINE: u32 = 3200;
\ No newline at end of file
+pub const CU_LIMIT_MINE: u32 = 3200;
+pub const CU_LIMIT_SOL_TRANSFER: u32 = 450;
+pub const CU_LIMIT_MEMO: u32 = 10_000;
+pub const CU_LIMIT_COMPUTE_BUDGET: u32 = 300;
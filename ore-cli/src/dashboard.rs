@@ -0,0 +1,318 @@
+use std::{
+    collections::VecDeque,
+    io::{stderr, stdout, IsTerminal, Read, Stderr, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use gag::BufferRedirect;
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Row, Table},
+    Frame, Terminal,
+};
+use solana_sdk::{native_token::lamports_to_sol, signature::Signature};
+
+use crate::{snapshot::ChainSnapshot, utils::format_ore_amount};
+
+// How often the dashboard is redrawn.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+// Window over which the live hashrate is averaged.
+const HASHRATE_WINDOW: Duration = Duration::from_secs(2);
+
+const MAX_SUBMISSIONS: usize = 10;
+const MAX_LOG_LINES: usize = 200;
+
+/// The outcome of one mine transaction submission.
+pub struct Submission {
+    pub at: DateTime<Local>,
+    pub signature: Option<Signature>,
+    pub outcome: String,
+    pub latency: Duration,
+}
+
+#[derive(Default)]
+struct DashboardState {
+    status: String,
+    snapshot: Option<ChainSnapshot>,
+    priority_fee: u64,
+    hashrate: f64,
+    earned: u64,
+    submissions: VecDeque<Submission>,
+    log: VecDeque<String>,
+}
+
+/// A terminal dashboard of the mining loop, drawn on stderr and redrawn in place by a
+/// background thread. While it runs, anything printed to a terminal stdout is captured
+/// into its log pane, and stdout redirected elsewhere is left untouched.
+pub struct Dashboard {
+    state: Arc<Mutex<DashboardState>>,
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Dashboard {
+    /// Takes over the terminal and starts drawing. `hashes` is the running count of
+    /// hashes computed, which the live hashrate is derived from.
+    pub fn start(hashes: Arc<AtomicU64>) -> Result<Self, String> {
+        if !stderr().is_terminal() {
+            return Err("--tui needs a terminal on stderr".to_string());
+        }
+        let captured = if stdout().is_terminal() {
+            Some(BufferRedirect::stdout().map_err(|err| err.to_string())?)
+        } else {
+            None
+        };
+        let terminal = enter_terminal().map_err(|err| err.to_string())?;
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        // Hand the terminal back before a panic message is printed.
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new({
+            let stopped = stopped.clone();
+            move |info| {
+                stopped.store(true, Ordering::Relaxed);
+                leave_terminal();
+                default_hook(info);
+            }
+        }));
+
+        let state = Arc::new(Mutex::new(DashboardState {
+            status: "Starting...".to_string(),
+            ..Default::default()
+        }));
+        let handle = std::thread::spawn({
+            let state = state.clone();
+            let stopped = stopped.clone();
+            move || draw_loop(terminal, captured, state, hashes, stopped)
+        });
+        Ok(Self {
+            state,
+            stopped,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn set_status(&self, status: &str) {
+        self.state.lock().unwrap().status = status.to_string();
+    }
+
+    pub fn set_snapshot(&self, snapshot: &ChainSnapshot) {
+        self.state.lock().unwrap().snapshot = Some(snapshot.clone());
+    }
+
+    pub fn set_priority_fee(&self, priority_fee: u64) {
+        self.state.lock().unwrap().priority_fee = priority_fee;
+    }
+
+    /// Records a submission, crediting `earned` ORE (in its smallest unit) to the session.
+    pub fn record_submission(&self, submission: Submission, earned: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.earned = state.earned.saturating_add(earned);
+        state.submissions.push_front(submission);
+        state.submissions.truncate(MAX_SUBMISSIONS);
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+fn enter_terminal() -> std::io::Result<Terminal<CrosstermBackend<Stderr>>> {
+    enable_raw_mode()?;
+    execute!(stderr(), EnterAlternateScreen)?;
+    Terminal::new(CrosstermBackend::new(stderr()))
+}
+
+fn leave_terminal() {
+    disable_raw_mode().ok();
+    execute!(stderr(), LeaveAlternateScreen).ok();
+}
+
+fn draw_loop(
+    mut terminal: Terminal<CrosstermBackend<Stderr>>,
+    mut captured: Option<BufferRedirect>,
+    state: Arc<Mutex<DashboardState>>,
+    hashes: Arc<AtomicU64>,
+    stopped: Arc<AtomicBool>,
+) {
+    let mut samples = VecDeque::from([(Instant::now(), hashes.load(Ordering::Relaxed))]);
+    loop {
+        // Raw mode swallows Ctrl-C, so quitting is handled here.
+        if event::poll(REFRESH_INTERVAL).unwrap_or(false) {
+            if let Ok(Event::Key(key)) = event::read() {
+                let ctrl_c = key.code == KeyCode::Char('c')
+                    && key.modifiers.contains(KeyModifiers::CONTROL);
+                if key.code == KeyCode::Char('q') || ctrl_c {
+                    drop(captured.take());
+                    leave_terminal();
+                    std::process::exit(0);
+                }
+            }
+        }
+        if stopped.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let now = Instant::now();
+        samples.push_back((now, hashes.load(Ordering::Relaxed)));
+        while samples.len() > 2 && now.duration_since(samples[1].0) >= HASHRATE_WINDOW {
+            samples.pop_front();
+        }
+        let mut state = state.lock().unwrap();
+        let (first_at, first_count) = samples[0];
+        let elapsed = now.duration_since(first_at).as_secs_f64();
+        if elapsed > 0.0 {
+            state.hashrate = (samples[samples.len() - 1].1 - first_count) as f64 / elapsed;
+        }
+        if let Some(captured) = captured.as_mut() {
+            let mut output = String::new();
+            captured.read_to_string(&mut output).ok();
+            for line in output.lines().filter(|line| !line.is_empty()) {
+                state.log.push_back(line.to_string());
+            }
+            while state.log.len() > MAX_LOG_LINES {
+                state.log.pop_front();
+            }
+        }
+        terminal.draw(|frame| render(frame, &state)).ok();
+    }
+
+    // Print what was captured but not shown, so nothing is lost on exit.
+    let mut output = String::new();
+    if let Some(mut captured) = captured {
+        captured.read_to_string(&mut output).ok();
+    }
+    leave_terminal();
+    stdout().write_all(output.as_bytes()).ok();
+}
+
+fn render(frame: &mut Frame, state: &DashboardState) {
+    let [summary, tables, log] = Layout::vertical([
+        Constraint::Length(7),
+        Constraint::Length(MAX_SUBMISSIONS as u16 + 3),
+        Constraint::Min(3),
+    ])
+    .areas(frame.size());
+    let [busses, submissions] =
+        Layout::horizontal([Constraint::Length(30), Constraint::Min(40)]).areas(tables);
+    render_summary(frame, summary, state);
+    render_busses(frame, busses, state);
+    render_submissions(frame, submissions, state);
+    render_log(frame, log, state);
+}
+
+fn render_summary(frame: &mut Frame, area: Rect, state: &DashboardState) {
+    let mut lines = vec![
+        Line::from(state.status.as_str().bold()),
+        Line::from(format!(
+            "Hashrate: {}    Priority fee: {} microlamports    Session earnings: {} ORE",
+            format_hashrate(state.hashrate),
+            state.priority_fee,
+            format_ore_amount(state.earned)
+        )),
+    ];
+    if let Some(snapshot) = &state.snapshot {
+        let proof = snapshot.proof.as_ref();
+        lines.push(Line::from(format!(
+            "Balance: {} ORE    Claimable: {} ORE    Reward rate: {} ORE    SOL: {}",
+            format_ore_amount(snapshot.token_balance.unwrap_or(0)),
+            format_ore_amount(proof.map_or(0, |proof| proof.claimable_rewards)),
+            format_ore_amount(snapshot.treasury.reward_rate),
+            lamports_to_sol(snapshot.sol_balance)
+        )));
+        if let Some(proof) = proof {
+            lines.push(Line::from(format!("Challenge:  {}", proof.hash)));
+        }
+        lines.push(Line::from(format!("Difficulty: {}", snapshot.treasury.difficulty)));
+    }
+    let block = Block::default().borders(Borders::ALL).title(" ORE miner (q to quit) ");
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn render_busses(frame: &mut Frame, area: Rect, state: &DashboardState) {
+    let rows: Vec<Row> = state
+        .snapshot
+        .iter()
+        .flat_map(|snapshot| snapshot.busses.iter())
+        .map(|bus| Row::new(vec![bus.id.to_string(), format_ore_amount(bus.rewards)]))
+        .collect();
+    let table = Table::new(rows, [Constraint::Length(4), Constraint::Min(10)])
+        .header(Row::new(vec!["Bus", "Rewards (ORE)"]).bold())
+        .block(Block::default().borders(Borders::ALL).title(" Busses "));
+    frame.render_widget(table, area);
+}
+
+fn render_submissions(frame: &mut Frame, area: Rect, state: &DashboardState) {
+    let rows: Vec<Row> = state
+        .submissions
+        .iter()
+        .map(|submission| {
+            let color = if submission.outcome.starts_with("landed") {
+                Color::Green
+            } else {
+                Color::Red
+            };
+            Row::new(vec![
+                submission.at.format("%H:%M:%S").to_string(),
+                submission.outcome.clone(),
+                format!("{:.1}s", submission.latency.as_secs_f64()),
+                submission
+                    .signature
+                    .map_or(String::new(), |signature| signature.to_string()),
+            ])
+            .style(Style::default().fg(color))
+        })
+        .collect();
+    let widths = [
+        Constraint::Length(8),
+        Constraint::Length(26),
+        Constraint::Length(8),
+        Constraint::Min(10),
+    ];
+    let table = Table::new(rows, widths)
+        .header(Row::new(vec!["Time", "Outcome", "Latency", "Signature"]).bold())
+        .block(Block::default().borders(Borders::ALL).title(" Submissions "));
+    frame.render_widget(table, area);
+}
+
+fn render_log(frame: &mut Frame, area: Rect, state: &DashboardState) {
+    let height = area.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = state
+        .log
+        .iter()
+        .skip(state.log.len().saturating_sub(height))
+        .map(|line| Line::from(line.as_str()))
+        .collect();
+    let block = Block::default().borders(Borders::ALL).title(" Log ");
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn format_hashrate(hashrate: f64) -> String {
+    if hashrate >= 1_000_000.0 {
+        format!("{:.2} MH/s", hashrate / 1_000_000.0)
+    } else if hashrate >= 1_000.0 {
+        format!("{:.2} kH/s", hashrate / 1_000.0)
+    } else {
+        format!("{:.0} H/s", hashrate)
+    }
+}
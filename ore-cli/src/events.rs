@@ -0,0 +1,163 @@
+//! JSON lines the miner prints to stdout for the orchestrator. Each carries its kind
+//! in `msg_type`.
+
+use serde::{Deserialize, Serialize};
+use solana_client::client_error::ClientError;
+use solana_sdk::signature::Signature;
+
+use crate::submit_error::SubmitError;
+
+/// Prints an event as a single JSON line.
+pub fn emit<T: Serialize>(event: &T) {
+    let json = serde_json::to_string(event).unwrap();
+    println!("{}", json);
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StartedMessage {
+    pub msg_type: String
+}
+
+impl StartedMessage {
+    pub fn new() -> Self {
+        Self {
+            msg_type: "started".to_string()
+        }
+    }
+}
+
+impl Default for StartedMessage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LandedMineMessage {
+    pub msg_type: String,
+}
+
+impl LandedMineMessage {
+    pub fn new() -> Self {
+        Self {
+            msg_type: "landed_mine_transaction".to_string(),
+        }
+    }
+}
+
+impl Default for LandedMineMessage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EpochResetMessage {
+    pub msg_type: String,
+    pub signature: String,
+}
+
+impl EpochResetMessage {
+    pub fn new(signature: Signature) -> Self {
+        Self {
+            msg_type: "epoch_reset".to_string(),
+            signature: signature.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FailedTransactionMessage {
+    pub msg_type: String,
+    pub error: String,
+    pub cause: String
+}
+
+impl FailedTransactionMessage {
+    pub fn new(error: &SubmitError) -> Self {
+        Self {
+            msg_type: "failed_transaction".to_string(),
+            error: error.to_string(),
+            cause: error.cause().to_string()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TokenAccountRegisteredMessage {
+    pub msg_type: String
+}
+
+impl TokenAccountRegisteredMessage {
+    pub fn new() -> Self {
+        Self {
+            msg_type: "token_account_registered".to_string()
+        }
+    }
+}
+
+impl Default for TokenAccountRegisteredMessage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ClaimedMessage {
+    pub msg_type: String,
+    pub amount: f64,
+    pub signature: String,
+}
+
+impl ClaimedMessage {
+    pub fn new(amount: f64, signature: Signature) -> Self {
+        Self {
+            msg_type: "claimed".to_string(),
+            amount,
+            signature: signature.to_string(),
+        }
+    }
+}
+
+/// Reports why an attempt did not land: the RPC node rejected it, it was not confirmed in
+/// time, its blockhash expired, or it executed and failed on-chain.
+#[derive(Serialize, Deserialize)]
+pub struct TransactionMessage {
+    pub msg_type: String,
+    pub signature: String,
+    pub error: Option<String>,
+}
+
+impl TransactionMessage {
+    pub fn expired(signature: Signature) -> Self {
+        Self {
+            msg_type: "transaction_expired".to_string(),
+            signature: signature.to_string(),
+            error: None,
+        }
+    }
+
+    pub fn rpc_rejected(signature: Signature, error: &ClientError) -> Self {
+        Self {
+            msg_type: "transaction_rpc_rejected".to_string(),
+            signature: signature.to_string(),
+            error: Some(error.to_string()),
+        }
+    }
+
+    pub fn dropped(signature: Signature) -> Self {
+        Self {
+            msg_type: "transaction_dropped".to_string(),
+            signature: signature.to_string(),
+            error: None,
+        }
+    }
+
+    pub fn failed(signature: Signature, error: &SubmitError) -> Self {
+        Self {
+            msg_type: "transaction_failed".to_string(),
+            signature: signature.to_string(),
+            error: Some(error.to_string()),
+        }
+    }
+}
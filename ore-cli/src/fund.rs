@@ -0,0 +1,253 @@
+use solana_program::system_instruction;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    native_token::lamports_to_sol,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    cu_limits::CU_LIMIT_SOL_TRANSFER,
+    keys::{keypair_name, keypair_paths, read_pubkey},
+    submit_error::SubmitError,
+    Miner,
+};
+
+// Transfers per transaction, keeping each transaction under the packet size limit.
+const TRANSFERS_PER_TX: usize = 20;
+
+// Accounts per getMultipleAccounts request.
+const ACCOUNTS_PER_REQUEST: usize = 100;
+
+// Lamports kept aside for the fees of the funding transactions.
+const FEE_RESERVE: u64 = 1_000_000;
+
+struct TopUp {
+    name: String,
+    pubkey: Pubkey,
+    balance: u64,
+    amount: u64,
+}
+
+impl Miner {
+    /// Tops every keypair in `keys_dir` up to `target` lamports, skipping shortfalls
+    /// below `min_transfer`. Returns the exit code.
+    pub async fn fund(&self, keys_dir: String, target: u64, min_transfer: u64, dry_run: bool) -> i32 {
+        // Read the pubkey of every keypair.
+        let paths = match keypair_paths(&keys_dir) {
+            Ok(paths) => paths,
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", keys_dir, err);
+                return 1;
+            }
+        };
+        let mut keys = vec![];
+        for path in paths.iter() {
+            match read_pubkey(path) {
+                Ok(pubkey) => keys.push((keypair_name(path), pubkey)),
+                Err(err) => {
+                    eprintln!("Failed to read {}: {}", path.display(), err);
+                    return 1;
+                }
+            }
+        }
+
+        // Fetch balances and compute the shortfall of each keypair.
+        let mut top_ups = vec![];
+        for chunk in keys.chunks(ACCOUNTS_PER_REQUEST) {
+            let pubkeys: Vec<Pubkey> = chunk.iter().map(|(_, pubkey)| *pubkey).collect();
+            let accounts = match self.client.get_multiple_accounts(&pubkeys).await {
+                Ok(response) => response.value,
+                Err(err) => {
+                    eprintln!("Error fetching balances: {}", err);
+                    return 1;
+                }
+            };
+            for ((name, pubkey), account) in chunk.iter().zip(accounts) {
+                let balance = account.map_or(0, |account| account.lamports);
+                top_ups.push(TopUp {
+                    name: name.clone(),
+                    pubkey: *pubkey,
+                    balance,
+                    amount: target.saturating_sub(balance),
+                });
+            }
+        }
+
+        // Print the plan.
+        println!("{:<24} {:<44} {:>14} {:>14}", "NAME", "PUBKEY", "SOL", "TOP UP");
+        for top_up in top_ups.iter() {
+            println!(
+                "{:<24} {:<44} {:>14} {:>14}",
+                top_up.name,
+                top_up.pubkey,
+                lamports_to_sol(top_up.balance),
+                lamports_to_sol(top_up.amount)
+            );
+        }
+        let transfers: Vec<(Pubkey, u64)> = top_ups
+            .iter()
+            .filter(|top_up| top_up.amount > 0 && top_up.amount >= min_transfer)
+            .map(|top_up| (top_up.pubkey, top_up.amount))
+            .collect();
+        let total: u64 = transfers.iter().map(|(_, amount)| amount).sum();
+        println!(
+            "\n{} transfers totalling {} SOL in {} transactions",
+            transfers.len(),
+            lamports_to_sol(total),
+            (transfers.len() + TRANSFERS_PER_TX - 1) / TRANSFERS_PER_TX
+        );
+        if dry_run || transfers.is_empty() {
+            return 0;
+        }
+
+        // Make sure the funding wallet covers the plan.
+        let funder = self.signer().pubkey();
+        match self.client.get_balance(&funder).await {
+            Ok(balance) if balance < total.saturating_add(FEE_RESERVE) => {
+                eprintln!(
+                    "Insufficient funds: {} holds {} SOL, {} SOL needed",
+                    funder,
+                    lamports_to_sol(balance),
+                    lamports_to_sol(total.saturating_add(FEE_RESERVE))
+                );
+                return 1;
+            }
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("Error fetching balance of {}: {}", funder, err);
+                return 1;
+            }
+        }
+
+        // Send batched transfers.
+        let mut code = 0;
+        for batch in transfers.chunks(TRANSFERS_PER_TX) {
+            // One extra transfer's worth of units covers the compute budget instructions.
+            let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(
+                CU_LIMIT_SOL_TRANSFER * (batch.len() as u32 + 1),
+            );
+            let cu_price_ix = ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee);
+            let mut ixs = vec![cu_limit_ix, cu_price_ix];
+            ixs.extend(system_instruction::transfer_many(&funder, batch));
+            let amount: u64 = batch.iter().map(|(_, amount)| amount).sum();
+            match self
+                .send_and_confirm_2(
+                    &ixs,
+                    false,
+                    self.confirm_retries,
+                    self.confirm_interval,
+                    self.gateway_retries,
+                )
+                .await
+            {
+                Ok(sig) => println!(
+                    "Funded {} keypairs with {} SOL: {}",
+                    batch.len(),
+                    lamports_to_sol(amount),
+                    sig
+                ),
+                Err(SubmitError::Simulated) => {
+                    println!("Funding {} keypairs simulated, not sent", batch.len())
+                }
+                Err(err) => {
+                    eprintln!("Failed to fund {} keypairs: {}", batch.len(), err);
+                    code = 1;
+                }
+            }
+        }
+        code
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use solana_sdk::{
+        native_token::LAMPORTS_PER_SOL, signature::Signer, system_program, transaction::Transaction,
+    };
+
+    use super::*;
+    use crate::mock_client::{test_dir, write_test_keypairs, MockChainClient};
+
+    // The recipients and lamports of the system transfers in `tx`.
+    fn transfers(tx: &Transaction) -> Vec<(Pubkey, u64)> {
+        tx.message
+            .instructions
+            .iter()
+            .filter(|ix| {
+                tx.message.account_keys[ix.program_id_index as usize] == system_program::ID
+            })
+            .map(|ix| {
+                let recipient = tx.message.account_keys[ix.accounts[1] as usize];
+                (
+                    recipient,
+                    u64::from_le_bytes(ix.data[4..12].try_into().unwrap()),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn tops_up_shortfalls_above_the_minimum() {
+        let (client, miner) = MockChainClient::funded();
+        client.set_balance(miner.signer().pubkey(), 10 * LAMPORTS_PER_SOL);
+        let dir = test_dir("fund");
+        let keypairs = write_test_keypairs(&dir, 4);
+        client.set_balance(keypairs[1].pubkey(), LAMPORTS_PER_SOL / 4);
+        client.set_balance(keypairs[2].pubkey(), LAMPORTS_PER_SOL - 1_000);
+        client.set_balance(keypairs[3].pubkey(), 2 * LAMPORTS_PER_SOL);
+        let dir_name = dir.to_string_lossy().to_string();
+        assert_eq!(
+            miner.fund(dir_name, LAMPORTS_PER_SOL, 10_000, false).await,
+            0
+        );
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(
+            transfers(&sent[0]),
+            [
+                (keypairs[0].pubkey(), LAMPORTS_PER_SOL),
+                (keypairs[1].pubkey(), LAMPORTS_PER_SOL * 3 / 4),
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn splits_transfers_across_transactions() {
+        let (client, miner) = MockChainClient::funded();
+        client.set_balance(miner.signer().pubkey(), 10 * LAMPORTS_PER_SOL);
+        let dir = test_dir("fund");
+        write_test_keypairs(&dir, TRANSFERS_PER_TX + 1);
+        let dir_name = dir.to_string_lossy().to_string();
+        assert_eq!(miner.fund(dir_name, 1_000_000, 0, false).await, 0);
+        let sent = client.sent_transactions();
+        let counts: Vec<usize> = sent.iter().map(|tx| transfers(tx).len()).collect();
+        assert_eq!(counts, [TRANSFERS_PER_TX, 1]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn sends_nothing_on_a_dry_run() {
+        let (client, miner) = MockChainClient::funded();
+        let dir = test_dir("fund");
+        write_test_keypairs(&dir, 2);
+        let dir_name = dir.to_string_lossy().to_string();
+        assert_eq!(miner.fund(dir_name, LAMPORTS_PER_SOL, 0, true).await, 0);
+        assert!(client.sent_transactions().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_plans_the_funder_cannot_cover() {
+        let (client, miner) = MockChainClient::funded();
+        let dir = test_dir("fund");
+        write_test_keypairs(&dir, 2);
+        // Two top ups of half the funder's balance leave nothing for fees.
+        let dir_name = dir.to_string_lossy().to_string();
+        assert_eq!(miner.fund(dir_name, 500_000_000, 0, false).await, 1);
+        assert!(client.sent_transactions().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,23 +1,36 @@
 use ore::TREASURY_ADDRESS;
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{commitment_config::CommitmentConfig, signature::Signer};
 
-use crate::Miner;
+use crate::{submit_error::SubmitError, Miner};
 
 impl Miner {
     pub async fn initialize(&self) {
+        match self.try_initialize().await {
+            Err(SubmitError::Simulated) => println!("Initialization simulated, not sent"),
+            result => {
+                result.expect("Transaction failed");
+            }
+        }
+    }
+
+    /// Initializes the program with the signer as admin, returning whether it had to be
+    /// initialized.
+    pub async fn try_initialize(&self) -> Result<bool, SubmitError> {
         // Return early if program is initialized
         let signer = self.signer();
-        let client =
-            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
-        if client.get_account(&TREASURY_ADDRESS).await.is_ok() {
-            return;
+        if self.client.get_account(&TREASURY_ADDRESS).await.is_ok() {
+            return Ok(false);
         }
 
         // Sign and send transaction.
         let ix = ore::instruction::initialize(signer.pubkey());
-        self.send_and_confirm(&[ix], false)
-            .await
-            .expect("Transaction failed");
+        self.send_and_confirm_2(
+            &[ix],
+            false,
+            self.confirm_retries,
+            self.confirm_interval,
+            self.gateway_retries,
+        )
+        .await?;
+        Ok(true)
     }
 }
@@ -0,0 +1,358 @@
+use std::{
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use futures::future::join_all;
+use solana_sdk::{
+    native_token::lamports_to_sol,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, write_keypair_file, Keypair, Signer},
+};
+
+use crate::{
+    keystore::{self, EncryptedKeypair},
+    signer::signer_from_source,
+    submit_error::SubmitError,
+    Miner,
+};
+
+// Lamports a keypair needs to pay for its proof and token account rent plus fees.
+const MIN_BOOTSTRAP_BALANCE: u64 = 5_000_000;
+
+/// Returns the keypair files of a keys directory, ordered the way the orchestrator
+/// assigns them to workers: by the number after the second underscore of the file name.
+pub fn keypair_paths(dir: &str) -> io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().map_or(false, |ext| ext == "json"))
+        .collect();
+    paths.sort_by_key(|path| (keypair_index(path), path.clone()));
+    Ok(paths)
+}
+
+fn keypair_index(path: &Path) -> Option<u64> {
+    let stem = path.file_stem()?.to_str()?;
+    stem.split('_').nth(2)?.parse().ok()
+}
+
+/// Reads the pubkey of a keypair file. Encrypted keypairs store it in the clear,
+/// so no passphrase is needed.
+pub fn read_pubkey(path: &Path) -> Result<Pubkey, Box<dyn Error>> {
+    match EncryptedKeypair::read(path) {
+        Some(encrypted) => Ok(Pubkey::from_str(&encrypted.pubkey)?),
+        None => Ok(read_keypair_file(path)?.pubkey()),
+    }
+}
+
+pub fn keypair_name(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+enum SetupStatus {
+    Existing,
+    Created,
+    Skipped,
+    Simulated,
+    Failed(String),
+}
+
+impl std::fmt::Display for SetupStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SetupStatus::Existing => write!(f, "existing"),
+            SetupStatus::Created => write!(f, "created"),
+            SetupStatus::Skipped => write!(f, "skipped"),
+            SetupStatus::Simulated => write!(f, "simulated, not sent"),
+            SetupStatus::Failed(err) => write!(f, "failed: {}", err),
+        }
+    }
+}
+
+struct BootstrapResult {
+    name: String,
+    pubkey: String,
+    balance: u64,
+    proof: SetupStatus,
+    ata: SetupStatus,
+}
+
+impl Miner {
+    /// Writes `count` new keypairs to `dir`, returning the exit code.
+    pub fn keys_generate(
+        &self,
+        count: u64,
+        dir: String,
+        prefix: String,
+        encrypt: bool,
+        passphrase_fd: Option<i32>,
+    ) -> i32 {
+        // The orchestrator reads the index from the third underscore separated field.
+        if prefix.split('_').count() != 2 {
+            eprintln!(
+                "Invalid prefix {:?}: expected exactly one underscore, e.g. id_miner",
+                prefix
+            );
+            return 2;
+        }
+        if let Err(err) = fs::create_dir_all(&dir) {
+            eprintln!("Failed to create {}: {}", dir, err);
+            return 1;
+        }
+        let passphrase = if encrypt {
+            match keystore::new_passphrase(passphrase_fd) {
+                Ok(passphrase) => Some(passphrase),
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    return 1;
+                }
+            }
+        } else {
+            None
+        };
+
+        // Continue numbering after the highest existing index for this prefix.
+        let first_index = keypair_paths(&dir)
+            .unwrap_or_default()
+            .iter()
+            .filter(|path| keypair_name(path).starts_with(&format!("{}_", prefix)))
+            .filter_map(|path| keypair_index(path))
+            .max()
+            .map_or(1, |index| index + 1);
+
+        for index in first_index..first_index + count {
+            let path = Path::new(&dir).join(format!("{}_{}.json", prefix, index));
+            let keypair = Keypair::new();
+            let result = match passphrase.as_deref() {
+                Some(passphrase) => EncryptedKeypair::encrypt(&keypair, passphrase)
+                    .and_then(|encrypted| encrypted.write(&path)),
+                None => write_keypair_file(&keypair, &path).map(|_| ()),
+            };
+            match result {
+                Ok(()) => println!("{:<32} {}", path.display(), keypair.pubkey()),
+                Err(err) => {
+                    eprintln!("Failed to write {}: {}", path.display(), err);
+                    return 1;
+                }
+            }
+        }
+        0
+    }
+
+    /// Registers a proof and creates a token account for every keypair in `dir`,
+    /// returning the exit code. It fails unless every keypair ends up ready to mine.
+    pub async fn keys_bootstrap(&self, dir: String, batch_size: usize, passphrase_fd: Option<i32>) -> i32 {
+        let paths = match keypair_paths(&dir) {
+            Ok(paths) => paths,
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", dir, err);
+                return 1;
+            }
+        };
+
+        // Load every keypair up front so a bad file fails before sending anything.
+        let mut miners = vec![];
+        for path in paths.iter() {
+            match signer_from_source(&path.to_string_lossy(), passphrase_fd) {
+                Ok(signer) => miners.push((keypair_name(path), self.with_signer(signer))),
+                Err(err) => {
+                    eprintln!("Failed to load {}: {}", path.display(), err);
+                    return 1;
+                }
+            }
+        }
+
+        let mut results = vec![];
+        for batch in miners.chunks(batch_size.max(1)) {
+            results.extend(
+                join_all(batch.iter().map(|(name, miner)| miner.bootstrap(name.clone()))).await,
+            );
+        }
+
+        println!(
+            "\n{:<24} {:<44} {:>12} {:<20} {:<20}",
+            "NAME", "PUBKEY", "SOL", "PROOF", "TOKEN ACCOUNT"
+        );
+        for result in results.iter() {
+            println!(
+                "{:<24} {:<44} {:>12} {:<20} {:<20}",
+                result.name,
+                result.pubkey,
+                lamports_to_sol(result.balance),
+                result.proof.to_string(),
+                result.ata.to_string()
+            );
+        }
+        // Simulated setups count as ready, so `--simulate-only` does not fail.
+        let failed = results
+            .iter()
+            .filter(|result| {
+                let ready = |status: &SetupStatus| {
+                    matches!(
+                        status,
+                        SetupStatus::Existing | SetupStatus::Created | SetupStatus::Simulated
+                    )
+                };
+                !ready(&result.proof) || !ready(&result.ata)
+            })
+            .count();
+        println!("\n{} of {} keypairs ready", results.len() - failed, results.len());
+        if failed > 0 {
+            1
+        } else {
+            0
+        }
+    }
+
+    async fn bootstrap(&self, name: String) -> BootstrapResult {
+        let pubkey = self.signer().pubkey();
+        let mut result = BootstrapResult {
+            name,
+            pubkey: pubkey.to_string(),
+            balance: 0,
+            proof: SetupStatus::Skipped,
+            ata: SetupStatus::Skipped,
+        };
+        match self.client.get_balance(&pubkey).await {
+            Ok(balance) => result.balance = balance,
+            Err(err) => {
+                result.proof = SetupStatus::Failed(err.to_string());
+                return result;
+            }
+        }
+        if result.balance < MIN_BOOTSTRAP_BALANCE {
+            result.proof = SetupStatus::Failed("insufficient SOL".to_string());
+            return result;
+        }
+        result.proof = match self.try_register().await {
+            Ok(true) => SetupStatus::Created,
+            Ok(false) => SetupStatus::Existing,
+            Err(SubmitError::Simulated) => SetupStatus::Simulated,
+            Err(err) => SetupStatus::Failed(err.to_string()),
+        };
+        result.ata = match self.try_initialize_ata().await {
+            Ok(true) => SetupStatus::Created,
+            Ok(false) => SetupStatus::Existing,
+            Err(SubmitError::Simulated) => SetupStatus::Simulated,
+            Err(err) => SetupStatus::Failed(err.to_string()),
+        };
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_client::{test_dir, test_passphrase, write_test_keypairs, MockChainClient};
+
+    fn names(dir: &Path) -> Vec<String> {
+        keypair_paths(&dir.to_string_lossy())
+            .unwrap()
+            .iter()
+            .map(|path| keypair_name(path))
+            .collect()
+    }
+
+    #[test]
+    fn generates_keypairs_numbered_after_existing_ones() {
+        let (_client, miner) = MockChainClient::funded();
+        let dir = test_dir("keys");
+        let dir_name = dir.to_string_lossy().to_string();
+        assert_eq!(
+            miner.keys_generate(10, dir_name.clone(), "id_miner".to_string(), false, None),
+            0
+        );
+        assert_eq!(
+            miner.keys_generate(2, dir_name, "id_miner".to_string(), false, None),
+            0
+        );
+        let expected: Vec<String> = (1..=12)
+            .map(|index| format!("id_miner_{}", index))
+            .collect();
+        assert_eq!(names(&dir), expected);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_prefixes_the_orchestrator_cannot_number() {
+        let (_client, miner) = MockChainClient::funded();
+        let dir = test_dir("keys");
+        let dir_name = dir.to_string_lossy().to_string();
+        for prefix in ["miner", "id_ore_miner"] {
+            assert_eq!(
+                miner.keys_generate(1, dir_name.clone(), prefix.to_string(), false, None),
+                2
+            );
+        }
+        assert!(names(&dir).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn generates_encrypted_keypairs() {
+        let (_client, miner) = MockChainClient::funded();
+        let dir = test_dir("keys");
+        test_passphrase();
+        let dir_name = dir.to_string_lossy().to_string();
+        assert_eq!(
+            miner.keys_generate(1, dir_name, "id_miner".to_string(), true, None),
+            0
+        );
+        let path = dir.join("id_miner_1.json");
+        let encrypted = EncryptedKeypair::read(&path).unwrap();
+        assert_eq!(read_pubkey(&path).unwrap().to_string(), encrypted.pubkey);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn bootstraps_every_funded_keypair() {
+        let (client, miner) = MockChainClient::funded();
+        let dir = test_dir("keys");
+        let keypairs = write_test_keypairs(&dir, 2);
+        for keypair in keypairs.iter() {
+            client.set_balance(keypair.pubkey(), MIN_BOOTSTRAP_BALANCE);
+        }
+        assert_eq!(
+            miner
+                .keys_bootstrap(dir.to_string_lossy().to_string(), 8, None)
+                .await,
+            0
+        );
+        // Each keypair registers a proof and creates its token account.
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 4);
+        for keypair in keypairs.iter() {
+            let paid = sent
+                .iter()
+                .filter(|tx| tx.message.account_keys[0] == keypair.pubkey());
+            assert_eq!(paid.count(), 2);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn fails_unless_every_keypair_is_ready() {
+        let (client, miner) = MockChainClient::funded();
+        let dir = test_dir("keys");
+        let keypairs = write_test_keypairs(&dir, 2);
+        client.set_balance(keypairs[0].pubkey(), MIN_BOOTSTRAP_BALANCE);
+        client.set_balance(keypairs[1].pubkey(), MIN_BOOTSTRAP_BALANCE - 1);
+        assert_eq!(
+            miner
+                .keys_bootstrap(dir.to_string_lossy().to_string(), 8, None)
+                .await,
+            1
+        );
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 2);
+        assert!(sent
+            .iter()
+            .all(|tx| tx.message.account_keys[0] == keypairs[0].pubkey()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,430 @@
+use std::{
+    env,
+    error::Error,
+    fs::{self, File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use solana_clap_utils::keypair::prompt_passphrase;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+
+use crate::Miner;
+
+/// Environment variable holding the passphrase of encrypted keypairs.
+pub const PASSPHRASE_ENV_VAR: &str = "ORE_KEYSTORE_PASSPHRASE";
+
+const KEYSTORE_VERSION: u8 = 1;
+const KDF_SCRYPT: &str = "scrypt";
+const CIPHER_AES_256_GCM: &str = "aes-256-gcm";
+
+// scrypt cost parameters for newly encrypted keypairs (N = 2^15).
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+static PASSPHRASE: OnceLock<String> = OnceLock::new();
+
+/// A keypair encrypted with a passphrase derived key. Binary fields are base58 encoded.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncryptedKeypair {
+    pub version: u8,
+    pub pubkey: String,
+    pub crypto: KeystoreCrypto,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KeystoreCrypto {
+    pub kdf: String,
+    pub kdfparams: ScryptParams,
+    pub cipher: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    pub salt: String,
+}
+
+impl EncryptedKeypair {
+    pub fn encrypt(keypair: &Keypair, passphrase: &str) -> Result<Self, Box<dyn Error>> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let kdfparams = ScryptParams {
+            log_n: SCRYPT_LOG_N,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+            salt: bs58::encode(salt).into_string(),
+        };
+        let cipher = Aes256Gcm::new(&derive_key(passphrase, &kdfparams)?.into());
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), keypair.to_bytes().as_slice())
+            .map_err(|_| "failed to encrypt keypair")?;
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            pubkey: keypair.pubkey().to_string(),
+            crypto: KeystoreCrypto {
+                kdf: KDF_SCRYPT.to_string(),
+                kdfparams,
+                cipher: CIPHER_AES_256_GCM.to_string(),
+                nonce: bs58::encode(nonce).into_string(),
+                ciphertext: bs58::encode(ciphertext).into_string(),
+            },
+        })
+    }
+
+    pub fn decrypt(&self, passphrase: &str) -> Result<Keypair, Box<dyn Error>> {
+        if self.version != KEYSTORE_VERSION
+            || self.crypto.kdf != KDF_SCRYPT
+            || self.crypto.cipher != CIPHER_AES_256_GCM
+        {
+            return Err(format!(
+                "unsupported keystore: version {}, {} with {}",
+                self.version, self.crypto.kdf, self.crypto.cipher
+            )
+            .into());
+        }
+        let nonce = bs58::decode(&self.crypto.nonce).into_vec()?;
+        if nonce.len() != NONCE_LEN {
+            return Err("invalid keystore nonce".into());
+        }
+        let ciphertext = bs58::decode(&self.crypto.ciphertext).into_vec()?;
+        let cipher = Aes256Gcm::new(&derive_key(passphrase, &self.crypto.kdfparams)?.into());
+        let bytes = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| "wrong passphrase or corrupted keystore")?;
+        let keypair = Keypair::from_bytes(&bytes)?;
+        if keypair.pubkey().to_string() != self.pubkey {
+            return Err("decrypted keypair does not match the keystore pubkey".into());
+        }
+        Ok(keypair)
+    }
+
+    /// Reads an encrypted keypair, returning `None` if the file is not a keystore.
+    pub fn read(path: &Path) -> Option<Self> {
+        let data = fs::read(path).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string_pretty(self)?;
+        write_private_file(path, json.as_bytes())
+    }
+}
+
+fn derive_key(passphrase: &str, params: &ScryptParams) -> Result<[u8; 32], Box<dyn Error>> {
+    let salt = bs58::decode(&params.salt).into_vec()?;
+    let scrypt_params = scrypt::Params::new(params.log_n, params.r, params.p, 32)
+        .map_err(|err| format!("invalid scrypt params: {}", err))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(passphrase.as_bytes(), &salt, &scrypt_params, &mut key)
+        .map_err(|err| format!("failed to derive key: {}", err))?;
+    Ok(key)
+}
+
+/// Returns the keystore passphrase from `ORE_KEYSTORE_PASSPHRASE`, the given file
+/// descriptor, or an interactive prompt, in that order. The first value read is reused
+/// for every keypair so a file descriptor is only consumed once.
+pub fn passphrase(passphrase_fd: Option<i32>) -> Result<&'static str, Box<dyn Error>> {
+    if let Some(passphrase) = PASSPHRASE.get() {
+        return Ok(passphrase);
+    }
+    let passphrase = if let Ok(passphrase) = env::var(PASSPHRASE_ENV_VAR) {
+        passphrase
+    } else if let Some(fd) = passphrase_fd {
+        read_passphrase_fd(fd)?
+    } else {
+        rpassword::prompt_password("Keystore passphrase: ")?
+    };
+    Ok(PASSPHRASE.get_or_init(|| passphrase))
+}
+
+#[cfg(unix)]
+fn read_passphrase_fd(fd: i32) -> Result<String, Box<dyn Error>> {
+    use std::os::unix::io::FromRawFd;
+    // Safety: the caller hands over the descriptor, which is closed once read.
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    let mut passphrase = String::new();
+    file.read_to_string(&mut passphrase)?;
+    Ok(passphrase.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(not(unix))]
+fn read_passphrase_fd(_fd: i32) -> Result<String, Box<dyn Error>> {
+    Err("--passphrase-fd is only supported on unix".into())
+}
+
+/// Writes a file only the owner can read. The contents go to a temporary file that is
+/// renamed over `path` once synced, so an existing key is never left half written.
+fn write_private_file(path: &Path, contents: &[u8]) -> Result<(), Box<dyn Error>> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("not a file path: {}", path.display()))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    let result = write_synced(&temp_path, contents).and_then(|()| {
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    });
+    if result.is_err() {
+        fs::remove_file(&temp_path).ok();
+    }
+    result
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+impl Miner {
+    /// Encrypts plaintext keypairs, returning the exit code: 1 if any failed.
+    pub fn keystore_import(
+        &self,
+        keypair_paths: Vec<String>,
+        out_dir: Option<String>,
+        passphrase_fd: Option<i32>,
+    ) -> i32 {
+        let passphrase = match new_passphrase(passphrase_fd) {
+            Ok(passphrase) => passphrase,
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                return 1;
+            }
+        };
+        let mut code = 0;
+        for keypair_path in keypair_paths {
+            let source = PathBuf::from(&keypair_path);
+            let target = match &out_dir {
+                Some(out_dir) => Path::new(out_dir).join(source.file_name().unwrap_or_default()),
+                None => source.clone(),
+            };
+            if EncryptedKeypair::read(&source).is_some() {
+                println!("Skipping {}: already encrypted", keypair_path);
+                continue;
+            }
+            let result = read_keypair_file(&source)
+                .and_then(|keypair| EncryptedKeypair::encrypt(&keypair, &passphrase))
+                .and_then(|encrypted| {
+                    if let Some(parent) = target.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    encrypted.write(&target)?;
+                    Ok(encrypted.pubkey)
+                });
+            match result {
+                Ok(pubkey) => println!("Encrypted {} ({}) to {}", keypair_path, pubkey, target.display()),
+                Err(err) => {
+                    eprintln!("Failed to import {}: {}", keypair_path, err);
+                    code = 1;
+                }
+            }
+        }
+        code
+    }
+
+    /// Decrypts a keystore, returning the exit code.
+    pub fn keystore_export(&self, keystore_path: String, out: Option<String>, passphrase_fd: Option<i32>) -> i32 {
+        let Some(encrypted) = EncryptedKeypair::read(Path::new(&keystore_path)) else {
+            eprintln!("Not an encrypted keypair: {}", keystore_path);
+            return 1;
+        };
+        let keypair = match passphrase(passphrase_fd).and_then(|passphrase| encrypted.decrypt(passphrase)) {
+            Ok(keypair) => keypair,
+            Err(err) => {
+                eprintln!("Failed to decrypt {}: {}", keystore_path, err);
+                return 1;
+            }
+        };
+        let json = serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap();
+        match out {
+            Some(out) => match write_private_file(Path::new(&out), json.as_bytes()) {
+                Ok(()) => println!("Exported {} to {}", encrypted.pubkey, out),
+                Err(err) => {
+                    eprintln!("Failed to write {}: {}", out, err);
+                    return 1;
+                }
+            },
+            None => println!("{}", json),
+        }
+        0
+    }
+
+    /// Lists the keypairs in a directory, returning the exit code.
+    pub fn keystore_list(&self, dir: String) -> i32 {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", dir, err);
+                return 1;
+            }
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .collect();
+        paths.sort();
+        for path in paths {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if let Some(encrypted) = EncryptedKeypair::read(&path) {
+                println!("{:<32} {:<44} encrypted", name, encrypted.pubkey);
+            } else if let Ok(keypair) = read_keypair_file(&path) {
+                println!("{:<32} {:<44} plaintext", name, keypair.pubkey());
+            }
+        }
+        0
+    }
+}
+
+/// Returns a passphrase to encrypt new keypairs with. Prompted ones are asked for twice so
+/// a typo does not lock the keys away, and empty ones are refused.
+pub fn new_passphrase(passphrase_fd: Option<i32>) -> Result<String, Box<dyn Error>> {
+    let passphrase = if env::var(PASSPHRASE_ENV_VAR).is_ok() || passphrase_fd.is_some() {
+        passphrase(passphrase_fd)?.to_string()
+    } else {
+        prompt_passphrase("New keystore passphrase: ")?
+    };
+    if passphrase.is_empty() {
+        return Err("passphrase must not be empty".into());
+    }
+    Ok(passphrase)
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signature::write_keypair_file;
+
+    use super::*;
+    use crate::mock_client::{test_dir, test_passphrase, write_test_keypairs, MockChainClient};
+
+    #[test]
+    fn encrypted_keypair_round_trip() {
+        let keypair = Keypair::new();
+        let encrypted = EncryptedKeypair::encrypt(&keypair, "correct horse").unwrap();
+        assert_eq!(encrypted.pubkey, keypair.pubkey().to_string());
+        let decrypted = encrypted.decrypt("correct horse").unwrap();
+        assert_eq!(decrypted.to_bytes(), keypair.to_bytes());
+        assert!(encrypted.decrypt("battery staple").is_err());
+    }
+
+    #[test]
+    fn private_file_replaces_existing_contents() {
+        let dir = test_dir("keystore");
+        let path = dir.join("id.json");
+        fs::write(&path, "plaintext").unwrap();
+        write_private_file(&path, b"encrypted").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "encrypted");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        // Only the key itself is left behind.
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn imports_and_exports_keypairs() {
+        let (_client, miner) = MockChainClient::funded();
+        let dir = test_dir("keystore");
+        let keypairs = write_test_keypairs(&dir, 2);
+        let passphrase = test_passphrase();
+        let paths: Vec<String> = (1..=2)
+            .map(|index| {
+                dir.join(format!("id_miner_{}.json", index))
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(miner.keystore_import(paths.clone(), None, None), 0);
+        for (path, keypair) in paths.iter().zip(keypairs.iter()) {
+            let encrypted = EncryptedKeypair::read(Path::new(path)).unwrap();
+            assert_eq!(
+                encrypted.decrypt(passphrase).unwrap().to_bytes(),
+                keypair.to_bytes()
+            );
+        }
+        // Encrypted keypairs are left alone.
+        let before = fs::read(&paths[0]).unwrap();
+        assert_eq!(miner.keystore_import(vec![paths[0].clone()], None, None), 0);
+        assert_eq!(fs::read(&paths[0]).unwrap(), before);
+
+        let out = dir.join("exported.json");
+        let out_name = out.to_string_lossy().to_string();
+        assert_eq!(
+            miner.keystore_export(paths[1].clone(), Some(out_name), None),
+            0
+        );
+        assert_eq!(
+            read_keypair_file(&out).unwrap().to_bytes(),
+            keypairs[1].to_bytes()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn imports_to_another_directory() {
+        let (_client, miner) = MockChainClient::funded();
+        let dir = test_dir("keystore");
+        let keypair = Keypair::new();
+        let path = dir.join("id.json");
+        write_keypair_file(&keypair, &path).unwrap();
+        test_passphrase();
+        let out_dir = dir.join("encrypted");
+        let out_dir_name = out_dir.to_string_lossy().to_string();
+        let path_name = path.to_string_lossy().to_string();
+        assert_eq!(
+            miner.keystore_import(vec![path_name], Some(out_dir_name), None),
+            0
+        );
+        // The plaintext keypair stays where it was.
+        assert_eq!(
+            read_keypair_file(&path).unwrap().to_bytes(),
+            keypair.to_bytes()
+        );
+        let encrypted = EncryptedKeypair::read(&out_dir.join("id.json")).unwrap();
+        assert_eq!(encrypted.pubkey, keypair.pubkey().to_string());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reports_keypairs_it_cannot_import_or_export() {
+        let (_client, miner) = MockChainClient::funded();
+        let dir = test_dir("keystore");
+        test_passphrase();
+        let missing = dir.join("missing.json").to_string_lossy().to_string();
+        assert_eq!(miner.keystore_import(vec![missing], None, None), 1);
+        let plaintext = dir.join("id.json");
+        write_keypair_file(&Keypair::new(), &plaintext).unwrap();
+        let plaintext = plaintext.to_string_lossy().to_string();
+        assert_eq!(miner.keystore_export(plaintext, None, None), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
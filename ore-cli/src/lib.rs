@@ -0,0 +1,209 @@
+//! Mining, claiming and querying ORE. The `ore` binary is a command line wrapper around
+//! this crate: build a [`Miner`] with [`Miner::builder`], then call its commands.
+//!
+//! The pieces of the mining loop are usable on their own: [`solver::Solver`] searches
+//! for hashes, [`snapshot`] reads program and account state, the `send_and_confirm*`
+//! methods of [`Miner`] submit transactions, and [`events`] holds the JSON lines
+//! printed for the orchestrator.
+//!
+//! Every RPC request goes through a [`chain_client::ChainClient`], so a miner can be
+//! served by `mock_client::MockChainClient`, built with the `test-utils` feature, to test
+//! commands offline.
+
+pub mod balance;
+pub mod busses;
+pub mod chain_client;
+mod claim;
+pub mod cu_limits;
+mod dashboard;
+pub mod dynamic_config;
+pub mod events;
+mod fund;
+#[cfg(feature = "admin")]
+mod initialize;
+pub mod keys;
+pub mod keystore;
+#[cfg(feature = "admin")]
+mod localnet;
+pub mod metrics;
+pub mod mine;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock_client;
+pub mod output;
+pub mod pubsub;
+mod register;
+pub mod reset;
+pub mod rewards;
+mod send_and_confirm;
+mod send_ore;
+pub mod signer;
+pub mod simulate;
+pub mod snapshot;
+pub mod solver;
+pub mod status;
+pub mod submit_error;
+mod sweep;
+pub mod treasury;
+#[cfg(feature = "admin")]
+mod update_admin;
+#[cfg(feature = "admin")]
+pub mod update_difficulty;
+pub mod utils;
+pub mod watcher;
+
+use std::sync::{Arc, Mutex};
+
+use chain_client::ChainClient;
+use pubsub::PubsubConnection;
+use solana_cli_config::ConfigInput;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signer};
+
+pub use mine::MineConfig;
+pub use send_and_confirm::MIN_SOL_BALANCE;
+use simulate::{CuLimitCache, Simulation};
+
+pub struct Miner {
+    pub(crate) signer: Option<Box<dyn Signer>>,
+    pub(crate) priority_fee: u64,
+    pub(crate) client: Arc<dyn ChainClient>,
+    pub(crate) pubsub: Arc<PubsubConnection>,
+    pub(crate) commitment: CommitmentConfig,
+    pub(crate) confirm_retries: usize,
+    pub(crate) confirm_interval: usize,
+    pub(crate) gateway_retries: usize,
+    pub(crate) simulation: Simulation,
+    pub(crate) cu_limit_cache: Arc<Mutex<CuLimitCache>>,
+}
+
+/// Configures a [`Miner`]. Everything but the RPC URL has the defaults of the `ore`
+/// binary.
+pub struct MinerBuilder {
+    cluster: String,
+    client: Option<Arc<dyn ChainClient>>,
+    websocket_url: Option<String>,
+    priority_fee: u64,
+    signer: Option<Box<dyn Signer>>,
+    commitment: CommitmentConfig,
+    confirm_retries: usize,
+    confirm_interval: usize,
+    gateway_retries: usize,
+    simulation: Simulation,
+}
+
+impl MinerBuilder {
+    /// Serves requests from `client` instead of the RPC node at the cluster URL, e.g. a
+    /// `MockChainClient` in tests.
+    pub fn client(mut self, client: Arc<dyn ChainClient>) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Websocket endpoint for confirmations and account watching. Defaults to one derived
+    /// from the RPC URL, and an empty one polls instead.
+    pub fn websocket_url(mut self, websocket_url: impl Into<String>) -> Self {
+        self.websocket_url = Some(websocket_url.into());
+        self
+    }
+
+    /// Microlamports paid per compute unit.
+    pub fn priority_fee(mut self, priority_fee: u64) -> Self {
+        self.priority_fee = priority_fee;
+        self
+    }
+
+    /// The keypair that signs and pays for transactions. Commands that sign panic
+    /// without one.
+    pub fn signer(mut self, signer: Box<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    pub fn commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.commitment = commitment;
+        self
+    }
+
+    pub fn confirm_retries(mut self, confirm_retries: usize) -> Self {
+        self.confirm_retries = confirm_retries;
+        self
+    }
+
+    /// Seconds between confirmation checks.
+    pub fn confirm_interval(mut self, confirm_interval: usize) -> Self {
+        self.confirm_interval = confirm_interval;
+        self
+    }
+
+    /// Times a transaction is sent before giving up on it.
+    pub fn gateway_retries(mut self, gateway_retries: usize) -> Self {
+        self.gateway_retries = gateway_retries;
+        self
+    }
+
+    pub fn simulation(mut self, simulation: Simulation) -> Self {
+        self.simulation = simulation;
+        self
+    }
+
+    pub fn build(self) -> Miner {
+        let websocket_url = self.websocket_url.unwrap_or_else(|| {
+            ConfigInput::compute_websocket_url_setting("", "", &self.cluster, "").1
+        });
+        let client = self.client.unwrap_or_else(|| {
+            Arc::new(RpcClient::new_with_commitment(self.cluster.clone(), self.commitment))
+        });
+        Miner {
+            signer: self.signer,
+            priority_fee: self.priority_fee,
+            client,
+            pubsub: Arc::new(PubsubConnection::new(websocket_url)),
+            commitment: self.commitment,
+            confirm_retries: self.confirm_retries,
+            confirm_interval: self.confirm_interval,
+            gateway_retries: self.gateway_retries,
+            simulation: self.simulation,
+            cu_limit_cache: Arc::new(Mutex::new(CuLimitCache::new())),
+        }
+    }
+}
+
+impl Miner {
+    /// Starts configuring a miner that talks to the RPC node at `cluster`.
+    pub fn builder(cluster: impl Into<String>) -> MinerBuilder {
+        MinerBuilder {
+            cluster: cluster.into(),
+            client: None,
+            websocket_url: None,
+            priority_fee: 0,
+            signer: None,
+            commitment: CommitmentConfig::confirmed(),
+            confirm_retries: 3,
+            confirm_interval: 4,
+            gateway_retries: 40,
+            simulation: Simulation::Off,
+        }
+    }
+
+    /// Returns a miner with the same settings that signs with another keypair.
+    pub fn with_signer(&self, signer: Box<dyn Signer>) -> Self {
+        Self {
+            signer: Some(signer),
+            priority_fee: self.priority_fee,
+            client: self.client.clone(),
+            pubsub: self.pubsub.clone(),
+            commitment: self.commitment,
+            confirm_retries: self.confirm_retries,
+            confirm_interval: self.confirm_interval,
+            gateway_retries: self.gateway_retries,
+            simulation: self.simulation,
+            cu_limit_cache: self.cu_limit_cache.clone(),
+        }
+    }
+
+    pub fn signer(&self) -> &dyn Signer {
+        self.signer
+            .as_deref()
+            .expect("Keypair is loaded at startup for commands that sign")
+    }
+}
@@ -0,0 +1,177 @@
+use std::{fs, net::IpAddr};
+
+use ore::{state::Treasury, utils::AccountDeserialize, TREASURY_ADDRESS};
+use serde::Serialize;
+use solana_program::{keccak::Hash as KeccakHash, pubkey, pubkey::Pubkey};
+
+use crate::{
+    keys::{keypair_name, keypair_paths},
+    update_difficulty::difficulty_from_leading_zero_bits,
+    Miner,
+};
+
+// Name prefix of the generated miner keypairs, numbered the way the orchestrator expects.
+const KEYPAIR_PREFIX: &str = "id_localnet";
+
+// The token metadata program, which `initialize` calls to describe the mint.
+const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+// A hash is found within a few hundred attempts.
+const LOCALNET_LEADING_ZERO_BITS: u32 = 8;
+
+/// The config read by the orchestrator, as in `configs/default.json`.
+#[derive(Serialize)]
+struct OrchestratorConfig {
+    ore_bin: String,
+    confirm_retries: usize,
+    confirm_interval: usize,
+    gateway_retries: usize,
+    keypairs_dir: String,
+    priority_fee_per_unit_mc_lamports: u64,
+    rpcs: Vec<String>,
+    fallback_rpc: String,
+    logs_dir: String,
+    thread_pool: bool,
+    dynamic_config: bool,
+}
+
+impl Miner {
+    /// Prepares a local validator for mining: initializes the program with the signer as
+    /// admin, lowers the difficulty, and generates `miners` keypairs in `keys_dir`, funded
+    /// with `lamports` each. Prints an orchestrator config mining with them.
+    pub async fn dev_localnet(
+        &self,
+        rpc_url: String,
+        miners: u64,
+        keys_dir: String,
+        lamports: u64,
+        ore_bin: String,
+        config_out: Option<String>,
+    ) {
+        if !is_local(&rpc_url) {
+            println!("Refusing to set up {}: not a local validator URL", rpc_url);
+            return;
+        }
+
+        // The validator has to load the program, and the token metadata program it calls.
+        let programs = [
+            (ore::ID, "ORE", "ore.so"),
+            (METADATA_PROGRAM_ID, "token metadata", "mpl_token_metadata.so"),
+        ];
+        let accounts = match self
+            .client
+            .get_multiple_accounts(&programs.map(|(id, _, _)| id))
+            .await
+        {
+            Ok(response) => response.value,
+            Err(err) => {
+                println!("Error fetching the programs: {}", err);
+                return;
+            }
+        };
+        for ((id, name, file), account) in programs.iter().zip(accounts) {
+            if !account.map_or(false, |account| account.executable) {
+                println!(
+                    "No {} program at {}: start the validator with --bpf-program {} <{}>",
+                    name, id, id, file
+                );
+                return;
+            }
+        }
+
+        // Initialize the program, unless someone already did.
+        match self.try_initialize().await {
+            Ok(true) => println!(
+                "Initialized the program, with {} as admin",
+                self.signer().pubkey()
+            ),
+            Ok(false) => println!("Program already initialized"),
+            Err(err) => {
+                println!("Failed to initialize the program: {}", err);
+                return;
+            }
+        }
+
+        // Lower the difficulty, which only the admin may.
+        let treasury = match self.client.get_account_data(&TREASURY_ADDRESS).await {
+            Ok(data) => *Treasury::try_from_bytes(&data).expect("Failed to parse treasury"),
+            Err(err) => {
+                println!("Error fetching the treasury: {}", err);
+                return;
+            }
+        };
+        let difficulty: KeccakHash = treasury.difficulty.into();
+        let localnet_difficulty = difficulty_from_leading_zero_bits(LOCALNET_LEADING_ZERO_BITS);
+        if difficulty == localnet_difficulty {
+            println!("Difficulty already set to {}", difficulty);
+        } else if treasury.admin != self.signer().pubkey() {
+            println!(
+                "Not lowering the difficulty: the admin is {}, not the signer",
+                treasury.admin
+            );
+        } else {
+            match self.try_update_difficulty(localnet_difficulty).await {
+                Ok(_) => println!("Difficulty set to {}", localnet_difficulty),
+                Err(err) => {
+                    println!("Failed to update the difficulty: {}", err);
+                    return;
+                }
+            }
+        }
+
+        // Top the keys directory up to `miners` keypairs, then fund them all.
+        let existing = keypair_paths(&keys_dir)
+            .unwrap_or_default()
+            .iter()
+            .filter(|path| keypair_name(path).starts_with(&format!("{}_", KEYPAIR_PREFIX)))
+            .count() as u64;
+        if existing < miners {
+            let count = miners - existing;
+            if self.keys_generate(count, keys_dir.clone(), KEYPAIR_PREFIX.into(), false, None) != 0 {
+                return;
+            }
+        }
+        if self.fund(keys_dir.clone(), lamports, 0, false).await != 0 {
+            return;
+        }
+
+        // Mine with every keypair over the local validator, at no priority fee.
+        let config = OrchestratorConfig {
+            ore_bin,
+            confirm_retries: self.confirm_retries,
+            confirm_interval: self.confirm_interval,
+            gateway_retries: self.gateway_retries,
+            keypairs_dir: keys_dir,
+            priority_fee_per_unit_mc_lamports: 0,
+            rpcs: vec![rpc_url.clone()],
+            fallback_rpc: rpc_url,
+            logs_dir: "logs".to_string(),
+            thread_pool: false,
+            dynamic_config: false,
+        };
+        let config = serde_json::to_string_pretty(&config).unwrap();
+        println!("\nOrchestrator config:\n{}", config);
+        if let Some(path) = config_out {
+            match fs::write(&path, format!("{}\n", config)) {
+                Ok(()) => println!("Wrote {}", path),
+                Err(err) => println!("Failed to write {}: {}", path, err),
+            }
+        }
+    }
+}
+
+/// Whether `url` points at this machine.
+fn is_local(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    match url.host_str() {
+        Some("localhost") => true,
+        Some(host) => host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .map_or(false, |ip| ip.is_loopback()),
+        None => false,
+    }
+}
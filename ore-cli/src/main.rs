@@ -1,54 +1,64 @@
-mod balance;
-mod busses;
-mod claim;
-mod cu_limits;
-#[cfg(feature = "admin")]
-mod initialize;
-mod mine;
-mod register;
-mod rewards;
-mod send_and_confirm;
-mod treasury;
-mod send_ore;
-#[cfg(feature = "admin")]
-mod update_admin;
-#[cfg(feature = "admin")]
-mod update_difficulty;
-mod utils;
-mod dynamic_config;
-
-use std::sync::Arc;
+use std::net::SocketAddr;
 
 use clap::{command, Parser, Subcommand};
-use solana_sdk::signature::{read_keypair_file, Keypair};
-
-struct Miner {
-    pub keypair_filepath: Option<String>,
-    pub priority_fee: u64,
-    pub cluster: String,
-    pub confirm_retries: usize,
-    pub confirm_interval: usize,
-    pub gateway_retries: usize,
-}
+use ore_cli::{
+    output::{print_result, OutputFormat},
+    reset::{Fleet, ResetPolicy},
+    signer,
+    simulate::Simulation,
+    utils, MineConfig, Miner,
+};
+#[cfg(feature = "admin")]
+use {
+    clap::ArgGroup,
+    ore_cli::update_difficulty::{parse_difficulty_hex, parse_solve_rate, DifficultyTarget},
+    solana_program::keccak::Hash as KeccakHash,
+};
+use solana_cli_config::{Config, ConfigInput, CONFIG_FILE};
+use solana_sdk::pubkey::Pubkey;
 
 #[derive(Parser, Debug)]
 #[command(about, version)]
 struct Args {
     #[arg(
         long,
-        value_name = "NETWORK_URL",
-        help = "Network address of your RPC provider",
-        default_value = "https://api.mainnet-beta.solana.com"
+        short = 'u',
+        alias = "url",
+        value_name = "URL_OR_MONIKER",
+        help = "Network address of your RPC provider, or a moniker: [m]ainnet-beta, [d]evnet, [t]estnet, [l]ocalhost. Defaults to the Solana CLI config"
     )]
-    rpc: String,
+    rpc: Option<String>,
 
     #[arg(
         long,
-        value_name = "KEYPAIR_FILEPATH",
-        help = "Filepath to keypair to use"
+        value_name = "WEBSOCKET_URL",
+        help = "Websocket endpoint for confirmations and account watching. Defaults to the Solana CLI config, then one derived from the RPC URL",
+        global = true
+    )]
+    ws: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "KEYPAIR_SOURCE",
+        help = "Filepath or URI of the keypair to use (file:, stdin:, prompt:, usb://ledger). Defaults to $ORE_KEYPAIR, then the Solana CLI config"
     )]
     keypair: Option<String>,
 
+    #[arg(
+        long = "config",
+        short = 'C',
+        value_name = "CONFIG_FILEPATH",
+        help = "Filepath to a Solana CLI config file. Defaults to ~/.config/solana/cli/config.yml"
+    )]
+    config_file: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FD",
+        help = "File descriptor to read the passphrase of encrypted keypairs from. Defaults to $ORE_KEYSTORE_PASSPHRASE, then a prompt"
+    )]
+    passphrase_fd: Option<i32>,
+
     #[arg(
         long,
         value_name = "MICROLAMPORTS",
@@ -83,6 +93,27 @@ struct Args {
         default_value = "40"
     )]
     gateway_retries: usize,
+
+    #[arg(
+        long,
+        help = "Simulate each kind of transaction once to size its compute unit limit"
+    )]
+    simulate: bool,
+
+    #[arg(
+        long,
+        help = "Simulate transactions and print their logs and compute units instead of sending them"
+    )]
+    simulate_only: bool,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format of the query commands: balance, busses, rewards, status and treasury",
+        default_value = "plain",
+        global = true
+    )]
+    output: OutputFormat,
 }
 
 #[derive(Subcommand, Debug)]
@@ -108,12 +139,27 @@ enum Commands {
     #[command(about = "Register ORE associated token account")]
     RegisterToken(RegisterTokenArgs),
 
+    #[command(about = "Top up the SOL balance of every keypair in a keys directory")]
+    Fund(FundArgs),
+
+    #[command(about = "Generate and set up miner keypairs")]
+    Keys(KeysArgs),
+
+    #[command(about = "Manage passphrase encrypted keypairs")]
+    Keystore(KeystoreArgs),
+
+    #[command(about = "Claim and send the ORE of every keypair in a keys directory to one wallet")]
+    Sweep(SweepArgs),
+
     #[command(about = "Fetch your balance of unclaimed mining rewards")]
     Rewards(RewardsArgs),
 
     #[command(about = "Fetch the treasury account and balance")]
     Treasury(TreasuryArgs),
 
+    #[command(about = "Summarise the balances, proof and mining readiness of accounts")]
+    Status(StatusArgs),
+
     #[cfg(feature = "admin")]
     #[command(about = "Initialize the program")]
     Initialize(InitializeArgs),
@@ -125,6 +171,10 @@ enum Commands {
     #[cfg(feature = "admin")]
     #[command(about = "Update the mining difficulty")]
     UpdateDifficulty(UpdateDifficultyArgs),
+
+    #[cfg(feature = "admin")]
+    #[command(about = "Set up environments for development")]
+    Dev(DevArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -166,7 +216,6 @@ struct MineArgs {
 
     #[arg(
         long,
-        short,
         value_name = "THREAD_POOL",
         help = "Use thread pool for mining",
         default_value = "false"
@@ -181,19 +230,98 @@ struct MineArgs {
         default_value = "false"
     )]
     dynamic_config: bool,
+
+    #[arg(
+        long,
+        value_name = "ORE",
+        help = "Claim rewards in the background once claimable rewards reach this amount",
+        value_parser = utils::parse_ore_amount
+    )]
+    auto_claim_threshold: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "TOKEN_ACCOUNT_ADDRESS",
+        help = "Token account to receive automatic claims. Defaults to your ORE token account",
+        requires = "auto_claim_threshold"
+    )]
+    auto_claim_to: Option<Pubkey>,
+
+    #[arg(
+        long,
+        help = "Watch account state over the websocket endpoint instead of polling every loop"
+    )]
+    watch: bool,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of miners sharing the duty of resetting epochs",
+        default_value = "1",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    fleet_size: u64,
+
+    #[arg(
+        long,
+        value_name = "INDEX",
+        help = "Position of this miner in the fleet, from 0 to fleet size minus one",
+        default_value = "0"
+    )]
+    fleet_index: u64,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Price of one ORE in SOL. Resets are only bundled when the hash earns more than their extra fee"
+    )]
+    ore_price: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Address to serve Prometheus metrics on, e.g. 0.0.0.0:9100"
+    )]
+    metrics_addr: Option<SocketAddr>,
+
+    #[arg(
+        long,
+        help = "Show a live dashboard instead of scrolling output. Press q to quit"
+    )]
+    tui: bool,
 }
 
 #[derive(Parser, Debug)]
 struct TreasuryArgs {}
 
+#[derive(Parser, Debug)]
+struct StatusArgs {
+    #[arg(
+        value_name = "ADDRESS",
+        help = "Addresses of the accounts to summarise. Defaults to your account"
+    )]
+    addresses: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Also summarise every keypair in this directory"
+    )]
+    keys_dir: Option<String>,
+
+    #[arg(long, help = "Print the summary as JSON. Same as --output json")]
+    json: bool,
+}
+
 #[derive(Parser, Debug)]
 struct ClaimArgs {
     #[arg(
         // long,
         value_name = "AMOUNT",
-        help = "The amount of rewards to claim. Defaults to max."
+        help = "The amount of rewards to claim. Defaults to max.",
+        value_parser = utils::parse_ore_amount
     )]
-    amount: Option<f64>,
+    amount: Option<u64>,
 
     #[arg(
         // long,
@@ -201,6 +329,14 @@ struct ClaimArgs {
         help = "Token account to receive mining rewards."
     )]
     beneficiary: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "WALLET_ADDRESS",
+        help = "Wallet whose ORE token account receives the rewards. The token account is created if needed",
+        conflicts_with = "beneficiary"
+    )]
+    to_owner: Option<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -210,11 +346,224 @@ struct SendOreArgs {
         help = "Wallet to which Ore is sent."
     )]
     recipient: String,
+
+    #[arg(
+        long,
+        value_name = "ORE",
+        help = "The amount of ORE to send. Defaults to your entire balance",
+        value_parser = utils::parse_ore_amount
+    )]
+    amount: Option<u64>,
+
+    #[arg(long, value_name = "TEXT", help = "Memo to attach to the transfer")]
+    memo: Option<String>,
+
+    #[arg(long, help = "Skip the confirmation prompt")]
+    yes: bool,
 }
 
 #[derive(Parser, Debug)]
 struct RegisterTokenArgs {}
 
+#[derive(Parser, Debug)]
+struct SweepArgs {
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory of keypairs to sweep",
+        default_value = "keys"
+    )]
+    keys_dir: String,
+
+    #[arg(
+        long,
+        value_name = "WALLET_ADDRESS",
+        help = "Wallet to receive the ORE. Its token account is created if needed"
+    )]
+    to: String,
+
+    #[arg(
+        long,
+        value_name = "ORE",
+        help = "Only claim rewards of keypairs with at least this much claimable",
+        default_value = "0",
+        value_parser = utils::parse_ore_amount
+    )]
+    min_claimable: u64,
+}
+
+#[derive(Parser, Debug)]
+struct FundArgs {
+    #[arg(
+        long,
+        value_name = "KEYPAIR_SOURCE",
+        help = "Funding wallet. Defaults to --keypair"
+    )]
+    from: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory of keypairs to fund",
+        default_value = "keys"
+    )]
+    keys_dir: String,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "SOL balance to top every keypair up to",
+        default_value = "0.05",
+        value_parser = utils::parse_sol_amount
+    )]
+    target: u64,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Skip keypairs whose shortfall is below this amount",
+        default_value = "0",
+        value_parser = utils::parse_sol_amount
+    )]
+    min_transfer: u64,
+
+    #[arg(
+        long,
+        help = "Print the funding plan without sending transactions",
+        default_value = "false"
+    )]
+    dry_run: bool,
+}
+
+#[derive(Parser, Debug)]
+struct KeysArgs {
+    #[command(subcommand)]
+    command: KeysCommands,
+}
+
+#[derive(Subcommand, Debug)]
+enum KeysCommands {
+    #[command(about = "Generate keypair files named for the orchestrator")]
+    Generate(KeysGenerateArgs),
+
+    #[command(about = "Register the proof and ORE token account of every keypair")]
+    Bootstrap(KeysBootstrapArgs),
+}
+
+#[derive(Parser, Debug)]
+struct KeysGenerateArgs {
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of keypairs to generate",
+        default_value = "1"
+    )]
+    count: u64,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory to write keypairs to",
+        default_value = "keys"
+    )]
+    dir: String,
+
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        help = "File name prefix, followed by _N.json",
+        default_value = "id_miner"
+    )]
+    prefix: String,
+
+    #[arg(
+        long,
+        help = "Encrypt the keypairs with the keystore passphrase",
+        default_value = "false"
+    )]
+    encrypt: bool,
+}
+
+#[derive(Parser, Debug)]
+struct KeysBootstrapArgs {
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory of keypairs to set up",
+        default_value = "keys"
+    )]
+    dir: String,
+
+    #[arg(
+        long,
+        value_name = "BATCH_SIZE",
+        help = "Number of keypairs to set up concurrently",
+        default_value = "10"
+    )]
+    batch_size: usize,
+}
+
+#[derive(Parser, Debug)]
+struct KeystoreArgs {
+    #[command(subcommand)]
+    command: KeystoreCommands,
+}
+
+#[derive(Subcommand, Debug)]
+enum KeystoreCommands {
+    #[command(about = "Encrypt plaintext keypair files")]
+    Import(KeystoreImportArgs),
+
+    #[command(about = "Decrypt an encrypted keypair file")]
+    Export(KeystoreExportArgs),
+
+    #[command(about = "List the keypairs in a directory and whether they are encrypted")]
+    List(KeystoreListArgs),
+}
+
+#[derive(Parser, Debug)]
+struct KeystoreImportArgs {
+    #[arg(
+        value_name = "KEYPAIR_FILEPATH",
+        help = "Plaintext keypair files to encrypt",
+        required = true
+    )]
+    keypairs: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory to write encrypted keypairs to. Defaults to replacing the files in place"
+    )]
+    out_dir: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+struct KeystoreExportArgs {
+    #[arg(
+        value_name = "KEYSTORE_FILEPATH",
+        help = "Encrypted keypair file to decrypt"
+    )]
+    keystore: String,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "File to write the plaintext keypair to. Defaults to stdout"
+    )]
+    out: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+struct KeystoreListArgs {
+    #[arg(
+        value_name = "DIR",
+        help = "Directory of keypair files",
+        default_value = "keys"
+    )]
+    dir: String,
+}
+
 #[cfg(feature = "admin")]
 #[derive(Parser, Debug)]
 struct InitializeArgs {}
@@ -227,53 +576,285 @@ struct UpdateAdminArgs {
 
 #[cfg(feature = "admin")]
 #[derive(Parser, Debug)]
-struct UpdateDifficultyArgs {}
+#[command(group(
+    ArgGroup::new("difficulty")
+        .required(true)
+        .args(["leading_zero_bits", "hex", "target_seconds"])
+))]
+struct UpdateDifficultyArgs {
+    #[arg(
+        long,
+        value_name = "BITS",
+        help = "Require hashes to start with this many zero bits",
+        value_parser = clap::value_parser!(u32).range(..=256)
+    )]
+    leading_zero_bits: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "HEX",
+        help = "The difficulty as 64 hex characters, most significant byte first",
+        value_parser = parse_difficulty_hex
+    )]
+    hex: Option<KeccakHash>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Average time to find a solution at --hashrate",
+        requires = "hashrate",
+        value_parser = parse_solve_rate
+    )]
+    target_seconds: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "HASHES_PER_SECOND",
+        help = "Hashrate of a miner, to target a solve time and estimate solve times at",
+        value_parser = parse_solve_rate
+    )]
+    hashrate: Option<f64>,
+
+    #[arg(long, help = "Skip the confirmation prompt")]
+    yes: bool,
+}
+
+#[cfg(feature = "admin")]
+#[derive(Parser, Debug)]
+struct DevArgs {
+    #[command(subcommand)]
+    command: DevCommands,
+}
+
+#[cfg(feature = "admin")]
+#[derive(Subcommand, Debug)]
+enum DevCommands {
+    #[command(
+        about = "Initialize the program on a local validator with an easy difficulty, and fund miner keypairs for the orchestrator"
+    )]
+    Localnet(LocalnetArgs),
+}
+
+#[cfg(feature = "admin")]
+#[derive(Parser, Debug)]
+struct LocalnetArgs {
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of miner keypairs",
+        default_value = "4"
+    )]
+    miners: u64,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory to keep the miner keypairs in",
+        default_value = "keys-localnet"
+    )]
+    keys_dir: String,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "SOL balance to fund every miner keypair to",
+        default_value = "10",
+        value_parser = utils::parse_sol_amount
+    )]
+    sol: u64,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Path of the ore binary in the orchestrator config",
+        default_value = "./bin/ore"
+    )]
+    ore_bin: String,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Also write the orchestrator config to this file"
+    )]
+    config_out: Option<String>,
+}
 
 #[tokio::main]
 async fn main() {
-    // Initialize miner.
+    // Load the Solana CLI config, falling back to its defaults.
     let args = Args::parse();
-    let cluster = args.rpc;
-    let miner = Arc::new(
-        Miner::new(
-            cluster.clone(), 
-            args.priority_fee, 
-            args.keypair,
-            args.confirm_retries,
-            args.confirm_interval,
-            args.gateway_retries,
-        )
+    let config = load_cli_config(args.config_file.as_deref());
+    let (_, cluster) = ConfigInput::compute_json_rpc_url_setting(
+        args.rpc.as_deref().unwrap_or_default(),
+        &config.json_rpc_url,
     );
+    let (_, websocket_url) = ConfigInput::compute_websocket_url_setting(
+        args.ws.as_deref().unwrap_or_default(),
+        if args.rpc.is_some() { "" } else { &config.websocket_url },
+        &cluster,
+        "",
+    );
+    let (_, commitment) = ConfigInput::compute_commitment_config("", &config.commitment);
+
+    // Load the signer once, failing fast. Commands without one never touch the keypair,
+    // so they do not prompt for passphrases.
+    let signer = if args.command.requires_signer() {
+        match signer::load_signer(
+            args.keypair.as_deref(),
+            &config.keypair_path,
+            args.passphrase_fd,
+        ) {
+            Ok(signer) => Some(signer),
+            Err(err) => {
+                eprintln!("Failed to load keypair: {}", err);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    // Initialize miner.
+    let passphrase_fd = args.passphrase_fd;
+    // The signer may hold a hardware wallet handle, so the miner stays on this task.
+    let mut builder = Miner::builder(cluster.clone())
+        .websocket_url(websocket_url)
+        .priority_fee(args.priority_fee)
+        .commitment(commitment)
+        .confirm_retries(args.confirm_retries)
+        .confirm_interval(args.confirm_interval)
+        .gateway_retries(args.gateway_retries)
+        .simulation(if args.simulate_only {
+            Simulation::Only
+        } else if args.simulate {
+            Simulation::Measure
+        } else {
+            Simulation::Off
+        });
+    if let Some(signer) = signer {
+        builder = builder.signer(signer);
+    }
+    let miner = builder.build();
 
     // Execute user command.
+    let output = args.output;
     match args.command {
         Commands::Balance(args) => {
-            miner.balance(args.address).await;
+            exit_on_error(print_result(output, miner.balance(args.address).await));
         }
         Commands::Busses(_) => {
-            miner.busses().await;
+            exit_on_error(print_result(output, miner.busses().await));
         }
         Commands::Rewards(args) => {
-            miner.rewards(args.address).await;
+            exit_on_error(print_result(output, miner.rewards(args.address).await));
         }
         Commands::Treasury(_) => {
-            miner.treasury().await;
+            exit_on_error(print_result(output, miner.treasury().await));
+        }
+        Commands::Status(args) => {
+            let output = if args.json { OutputFormat::Json } else { output };
+            let result = miner.status(args.addresses, args.keys_dir).await;
+            exit_on_error(print_result(output, result));
         }
         Commands::Register(_) => {
-            miner.register().await;
+            exit_on_error(miner.register().await);
         }
         Commands::Mine(args) => {
-            miner.mine(args.threads, args.thread_pool, args.dynamic_config).await;
+            if args.fleet_index >= args.fleet_size {
+                eprintln!(
+                    "Invalid fleet index {}: must be below the fleet size {}",
+                    args.fleet_index, args.fleet_size
+                );
+                std::process::exit(1);
+            }
+            exit_on_error(
+                miner
+                    .mine(MineConfig {
+                        threads: args.threads,
+                        thread_pool: args.thread_pool,
+                        dynamic_config: args.dynamic_config,
+                        auto_claim_threshold: args.auto_claim_threshold,
+                        auto_claim_to: args.auto_claim_to,
+                        watch: args.watch,
+                        reset_policy: ResetPolicy {
+                            fleet: Fleet {
+                                size: args.fleet_size,
+                                index: args.fleet_index,
+                            },
+                            ore_price: args.ore_price,
+                        },
+                        metrics_addr: args.metrics_addr,
+                        tui: args.tui,
+                    })
+                    .await,
+            );
         }
         Commands::Claim(args) => {
-            miner.claim(cluster, args.beneficiary, args.amount).await;
+            exit_on_error(
+                miner
+                    .claim(args.beneficiary, args.to_owner, args.amount)
+                    .await,
+            );
         }
         Commands::SendOre(args) => {
-            miner.send_ore(args.recipient).await;
+            exit_on_error(
+                miner
+                    .send_ore(args.recipient, args.amount, args.memo, args.yes)
+                    .await,
+            );
         }
         Commands::RegisterToken(_) => {
             miner.register_token_account().await;
         }
+        Commands::Fund(args) => {
+            let funder = match args.from {
+                Some(from) => match signer::signer_from_source(&from, passphrase_fd) {
+                    Ok(signer) => miner.with_signer(signer),
+                    Err(err) => {
+                        eprintln!("Failed to load funding keypair: {}", err);
+                        std::process::exit(1);
+                    }
+                },
+                None => miner,
+            };
+            exit_on_error(
+                funder
+                    .fund(args.keys_dir, args.target, args.min_transfer, args.dry_run)
+                    .await,
+            );
+        }
+        Commands::Sweep(args) => {
+            exit_on_error(
+                miner
+                    .sweep(args.keys_dir, args.to, args.min_claimable, passphrase_fd)
+                    .await,
+            );
+        }
+        Commands::Keys(args) => match args.command {
+            KeysCommands::Generate(args) => {
+                exit_on_error(miner.keys_generate(
+                    args.count,
+                    args.dir,
+                    args.prefix,
+                    args.encrypt,
+                    passphrase_fd,
+                ));
+            }
+            KeysCommands::Bootstrap(args) => {
+                exit_on_error(miner.keys_bootstrap(args.dir, args.batch_size, passphrase_fd).await);
+            }
+        },
+        Commands::Keystore(args) => match args.command {
+            KeystoreCommands::Import(args) => {
+                exit_on_error(miner.keystore_import(args.keypairs, args.out_dir, passphrase_fd));
+            }
+            KeystoreCommands::Export(args) => {
+                exit_on_error(miner.keystore_export(args.keystore, args.out, passphrase_fd));
+            }
+            KeystoreCommands::List(args) => {
+                exit_on_error(miner.keystore_list(args.dir));
+            }
+        },
         #[cfg(feature = "admin")]
         Commands::Initialize(_) => {
             miner.initialize().await;
@@ -283,35 +864,68 @@ async fn main() {
             miner.update_admin(args.new_admin).await;
         }
         #[cfg(feature = "admin")]
-        Commands::UpdateDifficulty(_) => {
-            miner.update_difficulty().await;
+        Commands::UpdateDifficulty(args) => {
+            let target = match (args.leading_zero_bits, args.hex, args.target_seconds) {
+                (Some(bits), _, _) => DifficultyTarget::LeadingZeroBits(bits),
+                (_, Some(difficulty), _) => DifficultyTarget::Exact(difficulty),
+                (_, _, Some(seconds)) => DifficultyTarget::SolveTime {
+                    seconds,
+                    hashrate: args.hashrate.unwrap(),
+                },
+                _ => unreachable!("clap requires one difficulty"),
+            };
+            miner.update_difficulty(target, args.hashrate, args.yes).await;
         }
+        #[cfg(feature = "admin")]
+        Commands::Dev(args) => match args.command {
+            DevCommands::Localnet(args) => {
+                miner
+                    .dev_localnet(
+                        cluster,
+                        args.miners,
+                        args.keys_dir,
+                        args.sol,
+                        args.ore_bin,
+                        args.config_out,
+                    )
+                    .await;
+            }
+        },
     }
 }
 
-impl Miner {
-    pub fn new(
-        cluster: String, 
-        priority_fee: u64, 
-        keypair_filepath: Option<String>,
-        confirm_retries: usize,
-        confirm_interval: usize,
-        gateway_retries: usize,
-    ) -> Self {
-        Self {
-            keypair_filepath,
-            priority_fee,
-            cluster,
-            confirm_retries,
-            confirm_interval,
-            gateway_retries,
+impl Commands {
+    fn requires_signer(&self) -> bool {
+        match self {
+            Commands::Busses(_)
+            | Commands::Treasury(_)
+            | Commands::Keys(_)
+            | Commands::Keystore(_) => false,
+            Commands::Balance(args) => args.address.is_none(),
+            Commands::Fund(args) => args.from.is_none(),
+            Commands::Rewards(args) => args.address.is_none(),
+            Commands::Status(args) => args.addresses.is_empty() && args.keys_dir.is_none(),
+            _ => true,
         }
     }
+}
+
+fn exit_on_error(code: i32) {
+    if code != 0 {
+        std::process::exit(code);
+    }
+}
 
-    pub fn signer(&self) -> Keypair {
-        match self.keypair_filepath.clone() {
-            Some(filepath) => read_keypair_file(filepath).unwrap(),
-            None => panic!("No keypair provided"),
-        }
+fn load_cli_config(config_file: Option<&str>) -> Config {
+    match config_file {
+        // An explicitly requested config must exist.
+        Some(config_file) => Config::load(config_file).unwrap_or_else(|err| {
+            eprintln!("Failed to load config file {}: {}", config_file, err);
+            std::process::exit(1);
+        }),
+        None => CONFIG_FILE
+            .as_ref()
+            .and_then(|config_file| Config::load(config_file).ok())
+            .unwrap_or_default(),
     }
 }
@@ -0,0 +1,136 @@
+use std::{
+    future::Future,
+    net::SocketAddr,
+    sync::OnceLock,
+    time::Instant,
+};
+
+use prometheus::{
+    exponential_buckets, Encoder, Gauge, Histogram, HistogramOpts, HistogramVec, IntCounter,
+    IntCounterVec, Opts, Registry, TextEncoder,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// Counters and gauges of the mining loop, served in the Prometheus text format.
+pub struct Metrics {
+    registry: Registry,
+    pub hashes: IntCounter,
+    pub hashrate: Gauge,
+    pub solutions: IntCounter,
+    pub submissions_attempted: IntCounter,
+    pub submissions_landed: IntCounter,
+    pub submissions_failed: IntCounterVec,
+    pub fees_spent: IntCounter,
+    pub ore_earned: Gauge,
+    pub sol_balance: Gauge,
+    pub rpc_latency: HistogramVec,
+    pub time_to_land: Histogram,
+}
+
+/// Returns the process wide metrics, registering them on first use.
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(|| {
+        let registry = Registry::new_custom(Some("ore".to_string()), None).unwrap();
+        let metrics = Metrics {
+            hashes: IntCounter::new("hashes_total", "Hashes computed").unwrap(),
+            hashrate: Gauge::new("hashrate", "Hashes per second while finding the last solution")
+                .unwrap(),
+            solutions: IntCounter::new("solutions_total", "Valid hashes found").unwrap(),
+            submissions_attempted: IntCounter::new(
+                "submissions_attempted_total",
+                "Mine transactions submitted",
+            )
+            .unwrap(),
+            submissions_landed: IntCounter::new(
+                "submissions_landed_total",
+                "Mine transactions landed",
+            )
+            .unwrap(),
+            submissions_failed: IntCounterVec::new(
+                Opts::new("submissions_failed_total", "Mine transactions failed, by cause"),
+                &["cause"],
+            )
+            .unwrap(),
+            fees_spent: IntCounter::new(
+                "fees_spent_lamports_total",
+                "Lamports paid in fees by landed transactions",
+            )
+            .unwrap(),
+            ore_earned: Gauge::new("earned_ore_total", "ORE earned by landed mine transactions")
+                .unwrap(),
+            sol_balance: Gauge::new("sol_balance", "SOL balance of the signer").unwrap(),
+            rpc_latency: HistogramVec::new(
+                HistogramOpts::new("rpc_latency_seconds", "Latency of RPC requests, by method")
+                    .buckets(exponential_buckets(0.01, 2.0, 12).unwrap()),
+                &["method"],
+            )
+            .unwrap(),
+            time_to_land: Histogram::with_opts(
+                HistogramOpts::new(
+                    "time_to_land_seconds",
+                    "Time from first sending a transaction until it is confirmed",
+                )
+                .buckets(exponential_buckets(0.25, 2.0, 10).unwrap()),
+            )
+            .unwrap(),
+            registry,
+        };
+        metrics.registry.register(Box::new(metrics.hashes.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.hashrate.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.solutions.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.submissions_attempted.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.submissions_landed.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.submissions_failed.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.fees_spent.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.ore_earned.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.sol_balance.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.rpc_latency.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.time_to_land.clone())).unwrap();
+        metrics
+    })
+}
+
+/// Awaits an RPC request, recording its latency under `method`.
+pub async fn time_rpc<T>(method: &str, request: impl Future<Output = T>) -> T {
+    let start = Instant::now();
+    let result = request.await;
+    metrics()
+        .rpc_latency
+        .with_label_values(&[method])
+        .observe(start.elapsed().as_secs_f64());
+    result
+}
+
+/// Serves the metrics over HTTP on `addr` from a background task.
+pub async fn serve(addr: SocketAddr) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    println!("Serving metrics on http://{}/metrics", addr);
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                continue;
+            };
+            tokio::spawn(async move {
+                // Every path serves the metrics, so the request itself is not parsed.
+                let mut request = [0u8; 1024];
+                stream.read(&mut request).await.ok();
+                let mut body = vec![];
+                let encoder = TextEncoder::new();
+                encoder.encode(&metrics().registry.gather(), &mut body).ok();
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    encoder.format_type(),
+                    body.len()
+                );
+                stream.write_all(header.as_bytes()).await.ok();
+                stream.write_all(&body).await.ok();
+            });
+        }
+    });
+    Ok(())
+}
@@ -1,384 +1,615 @@
 use std::{
-    io::{stdout, Write}, os::unix::thread, sync::{atomic::AtomicBool, Arc, Mutex}
+    cell::Cell,
+    io::{stdout, Write},
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
-use ore::{self, state::{Bus, Treasury}, BUS_ADDRESSES, BUS_COUNT, EPOCH_DURATION};
+use chrono::Local;
+use futures::StreamExt;
+
+use ore::{self, error::OreError, state::Bus, BUS_ADDRESSES};
 use rand::Rng;
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_client::client_error::ClientError;
 
 use solana_sdk::{
-    commitment_config::CommitmentConfig, compute_budget::ComputeBudgetInstruction, instruction::Instruction, keccak::{hashv, Hash as KeccakHash}, signature::{Keypair, Signer},
-    keccak::Hash
+    compute_budget::ComputeBudgetInstruction, instruction::Instruction, keccak::Hash,
+    native_token::lamports_to_sol, pubkey::Pubkey, signature::Signature,
 };
 
 use crate::{
-    cu_limits::{CU_LIMIT_MINE, CU_LIMIT_RESET}, dynamic_config::{self, DynamicConfig}, treasury, utils::{get_clock_account, get_proof, get_treasury}, Miner
+    cu_limits::{CU_LIMIT_MINE, CU_LIMIT_RESET},
+    dashboard::{Dashboard, Submission},
+    dynamic_config::DynamicConfig,
+    events::{
+        emit, EpochResetMessage, FailedTransactionMessage, LandedMineMessage, StartedMessage,
+    },
+    metrics::metrics,
+    reset::ResetPolicy,
+    send_and_confirm::MIN_SOL_BALANCE,
+    simulate::Simulation,
+    snapshot::ChainSnapshot,
+    solver::Solver,
+    submit_error::SubmitError,
+    utils::format_ore_amount,
+    watcher::AccountWatcher,
+    Miner,
 };
 
-use serde::{Serialize, Deserialize};
+// Seconds to wait before retrying a submission that cannot succeed yet
+const RETRY_DELAY_SECS: u64 = 2;
+
+// What the last built mine transaction carried.
+#[derive(Clone, Copy, Default)]
+struct MineAttempt {
+    reset: bool,
+    priority_fee: u64,
+}
 
-use rayon::prelude::*;
+/// How [`Miner::mine`] mines. The defaults mine on one thread, polling for state, with
+/// nothing but the mining loop running.
+#[derive(Clone, Copy, Debug)]
+pub struct MineConfig {
+    /// Threads searching for hashes.
+    pub threads: u64,
+    /// Search on a rayon pool of `threads` threads instead of fresh threads per hash.
+    pub thread_pool: bool,
+    /// Pick busses and priority fees from the local config server.
+    pub dynamic_config: bool,
+    /// Claim rewards in the background once this much is claimable, in base units.
+    pub auto_claim_threshold: Option<u64>,
+    /// Token account receiving automatic claims. Defaults to the signer's.
+    pub auto_claim_to: Option<Pubkey>,
+    /// Watch account state over the miner's websocket instead of polling every loop.
+    pub watch: bool,
+    pub reset_policy: ResetPolicy,
+    /// Serve Prometheus metrics on this address.
+    pub metrics_addr: Option<SocketAddr>,
+    /// Draw the terminal dashboard.
+    pub tui: bool,
+}
 
-// Odds of being selected to submit a reset tx
-const RESET_ODDS: u64 = 20;
+impl Default for MineConfig {
+    fn default() -> Self {
+        Self {
+            threads: 1,
+            thread_pool: false,
+            dynamic_config: false,
+            auto_claim_threshold: None,
+            auto_claim_to: None,
+            watch: false,
+            reset_policy: ResetPolicy::default(),
+            metrics_addr: None,
+            tui: false,
+        }
+    }
+}
 
 impl Miner {
-    pub async fn mine(&self, threads: u64, thread_pool: bool, dynamic_config: bool) {
-        let num_global_threads = threads.try_into().unwrap();
+    /// Mines until a simulated submission or an unrecoverable error, returning the exit
+    /// code. Errors are reported once the dashboard has handed the terminal back.
+    pub async fn mine(&self, config: MineConfig) -> i32 {
+        match self.try_mine(config).await {
+            Ok(()) => 0,
+            Err(err) => {
+                eprintln!("{}", err);
+                1
+            }
+        }
+    }
 
-        if thread_pool {
-            rayon::ThreadPoolBuilder::new().num_threads(num_global_threads).build_global().unwrap();   
+    async fn try_mine(&self, config: MineConfig) -> Result<(), String> {
+        let MineConfig {
+            threads,
+            thread_pool,
+            dynamic_config,
+            auto_claim_threshold,
+            auto_claim_to,
+            watch,
+            reset_policy,
+            metrics_addr,
+            tui,
+        } = config;
+        let solver = Solver::new(threads).thread_pool(thread_pool);
+
+        if let Some(addr) = metrics_addr {
+            if let Err(err) = crate::metrics::serve(addr).await {
+                return Err(format!("Failed to serve metrics on {}: {}", addr, err));
+            }
         }
 
+        // Draw the dashboard before anything else is printed, so it captures the output.
+        let hashes = Arc::new(AtomicU64::new(0));
+        let dashboard = if tui {
+            match Dashboard::start(hashes.clone()) {
+                Ok(dashboard) => Some(dashboard),
+                Err(err) => return Err(format!("Failed to start the dashboard: {}", err)),
+            }
+        } else {
+            None
+        };
+
         self.send_started_message();
 
         // Register, if needed.
-        let signer = Arc::new(self.signer());
-        self.register().await;
+        let pubkey = self.signer().pubkey();
+        match self.try_register().await {
+            Ok(_) => {}
+            Err(SubmitError::Simulated) => {
+                println!("Registration simulated, not sent");
+                return Ok(());
+            }
+            Err(err) => return Err(format!("Failed to register: {}", err)),
+        }
         let mut stdout = stdout();
-        // let mut rng = rand::thread_rng();
-
-        // Start mining loop
-        loop {
-            // Fetch account state
-            let balance = self.get_ore_display_balance().await;
-            let treasury = get_treasury(self.cluster.clone()).await;
-            let proof = get_proof(self.cluster.clone(), signer.pubkey()).await;
-            let rewards =
-                (proof.claimable_rewards as f64) / (10f64.powf(ore::TOKEN_DECIMALS as f64));
-            let reward_rate =
-                (treasury.reward_rate as f64) / (10f64.powf(ore::TOKEN_DECIMALS as f64));
-            stdout.write_all(b"\x1b[2J\x1b[3J\x1b[H").ok();
-            println!("Balance: {} ORE", balance);
-            println!("Claimable: {} ORE", rewards);
-            println!("Reward rate: {} ORE", reward_rate);
-
-            // Escape sequence that clears the screen and the scrollback buffer
-            println!("\nMining for a valid hash...");
-            let (next_hash, nonce) = if thread_pool {
-                self.find_next_hash_par_2(proof.hash.into(), treasury.difficulty.into(), threads)
-            } else {
-                self.find_next_hash_par(proof.hash.into(), treasury.difficulty.into(), threads)
-            };
-
-            // Submit mine tx.
-            // Use busses randomly so on each epoch, transactions don't pile on the same busses
-            println!("\n\nSubmitting hash for validation...");
-            loop {
 
-                // Reset epoch, if needed
-                let treasury = get_treasury(self.cluster.clone()).await;
-                // let clock = get_clock_account(self.cluster.clone()).await;
-                // let threshold = treasury.last_reset_at.saturating_add(EPOCH_DURATION);
-
-                // if clock.unix_timestamp.ge(&threshold) {
-                //     // There are a lot of miners right now, so randomly select into submitting tx
-                //     if rng.gen_range(0..RESET_ODDS).eq(&0) {
-                //         println!("Sending epoch reset transaction...");
-                //         let cu_limit_ix =
-                //             ComputeBudgetInstruction::set_compute_unit_limit(CU_LIMIT_RESET);
-                //         let cu_price_ix =
-                //             ComputeBudgetInstruction::set_compute_unit_price(priority_fee);
-                //         let reset_ix = ore::instruction::reset(signer.pubkey());
-                //         self.send_and_confirm_2(
-                //             &[cu_limit_ix, cu_price_ix, reset_ix], 
-                //             true,
-                //             self.confirm_retries,
-                //             self.gateway_retries,
-                //         )
-                //         .await
-                //         .ok();
-                //     }
-                // }
-
-                match self.send_and_confirm_3(
-                    || async { 
-                        self.build_instructions(
-                            dynamic_config, 
-                            signer.clone(), 
-                            treasury,
-                            next_hash, 
-                            nonce
-                        ).await
+        // Resolve where automatic claims go, creating the token account up front.
+        let auto_claim = match auto_claim_threshold {
+            Some(threshold) => {
+                let beneficiary = match auto_claim_to {
+                    Some(to) => to,
+                    None => match self.try_initialize_ata().await {
+                        Ok(_) => spl_associated_token_account::get_associated_token_address(
+                            &pubkey,
+                            &ore::MINT_ADDRESS,
+                        ),
+                        Err(err) => {
+                            return Err(format!(
+                                "Failed to create the token account for auto-claims: {}",
+                                err
+                            ));
+                        }
                     },
-                    false,
-                    self.confirm_retries,
-                    self.confirm_interval,
-                    self.gateway_retries,
-                )
-                .await
+                };
+                Some((threshold, beneficiary))
+            }
+            None => None,
+        };
+
+        // Claims run one at a time next to the mining loop, which hands them over without
+        // waiting for them to confirm.
+        let claim_in_flight = &Cell::new(false);
+        let (claim_sender, mut claim_receiver) = futures::channel::mpsc::unbounded::<u64>();
+        let claiming = async {
+            while let Some(amount) = claim_receiver.next().await {
+                if let Some((_, beneficiary)) = auto_claim {
+                    self.auto_claim(beneficiary, amount).await;
+                }
+                claim_in_flight.set(false);
+            }
+        };
+
+        // Watch account state over the websocket, if asked to.
+        let watcher = (watch && !self.pubsub.url().is_empty()).then(|| {
+            AccountWatcher::spawn(
+                self.pubsub.clone(),
+                self.client.clone(),
+                self.commitment,
+                pubkey,
+            )
+        });
+        let mut last_hash = None;
+
+        // Start mining loop. Dropping the claim sender when it stops ends the claims.
+        let mining = async move {
+            loop {
+                // Fetch account state
+                let snapshot = match self
+                    .get_watched_snapshot(watcher.as_ref(), pubkey, last_hash)
+                    .await
                 {
-                    Ok(sig) => {
-                        println!("Success: {}", sig);
-                        self.send_landed_mine_message();
-                        break;
+                    Ok(snapshot) => snapshot,
+                    Err(err) => {
+                        println!("Error fetching account state: {:?}", err);
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        continue;
                     }
-                    Err(_err) => {
-                        // TODO
-                        self.send_failed_transaction_message(_err);
+                };
+                if snapshot.sol_balance <= MIN_SOL_BALANCE {
+                    self.send_failed_transaction_message(&SubmitError::InsufficientBalance);
+                    return Err(format!(
+                        "Too low balance: {} SOL",
+                        lamports_to_sol(snapshot.sol_balance)
+                    ));
+                }
+                metrics()
+                    .sol_balance
+                    .set(lamports_to_sol(snapshot.sol_balance));
+                let balance = format_ore_amount(snapshot.token_balance.unwrap_or(0));
+                let treasury = snapshot.treasury;
+                // The node may not have caught up with the registration yet.
+                let Some(proof) = snapshot.proof else {
+                    println!("Proof account not found, retrying shortly");
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                };
+                let rewards =
+                    (proof.claimable_rewards as f64) / (10f64.powf(ore::TOKEN_DECIMALS as f64));
+                let reward_rate =
+                    (treasury.reward_rate as f64) / (10f64.powf(ore::TOKEN_DECIMALS as f64));
+                if let Some(dashboard) = &dashboard {
+                    dashboard.set_snapshot(&snapshot);
+                    dashboard.set_status("Mining for a valid hash...");
+                } else {
+                    // Escape sequence that clears the screen and the scrollback buffer
+                    stdout.write_all(b"\x1b[2J\x1b[3J\x1b[H").ok();
+                }
+                println!("Balance: {} ORE", balance);
+                println!("Claimable: {} ORE", rewards);
+                println!("Reward rate: {} ORE", reward_rate);
+
+                println!("\nMining for a valid hash...");
+                let hash = proof.hash.into();
+                let difficulty = treasury.difficulty.into();
+                let hashing_started = Instant::now();
+                let hashes_before = hashes.load(Ordering::Relaxed);
+                let hashing = tokio::task::spawn_blocking({
+                    let hashes = hashes.clone();
+                    let solver = solver.clone();
+                    move || solver.solve(pubkey, hash, difficulty, hashes)
+                });
+
+                // Hand a claim over once rewards cross the threshold, unless one is pending.
+                if let Some((threshold, _)) = auto_claim {
+                    if proof.claimable_rewards.ge(&threshold) && !claim_in_flight.get() {
+                        claim_in_flight.set(true);
+                        claim_sender.unbounded_send(proof.claimable_rewards).ok();
+                    }
+                }
+                let (next_hash, nonce) = hashing.await.expect("Hashing thread panicked");
+                let hashed = hashes.load(Ordering::Relaxed) - hashes_before;
+                metrics().hashes.inc_by(hashed);
+                metrics()
+                    .hashrate
+                    .set(hashed as f64 / hashing_started.elapsed().as_secs_f64());
+                metrics().solutions.inc();
+
+                // Submit mine tx.
+                // Use busses randomly so on each epoch, transactions don't pile on the same busses
+                println!("\n\nSubmitting hash for validation...");
+                if let Some(dashboard) = &dashboard {
+                    dashboard.set_status("Submitting hash for validation...");
+                }
+                loop {
+                    // The instructions are rebuilt per attempt, and may carry an epoch reset.
+                    let attempt = Cell::new(MineAttempt::default());
+                    let submitted_at = Instant::now();
+                    metrics().submissions_attempted.inc();
+                    let result = self
+                        .send_and_confirm_3(
+                            || async {
+                                self.build_instructions(
+                                    dynamic_config,
+                                    watcher.as_ref(),
+                                    &snapshot,
+                                    (next_hash, nonce),
+                                    reset_policy,
+                                    &attempt,
+                                )
+                                .await
+                            },
+                            false,
+                            self.confirm_retries,
+                            self.confirm_interval,
+                            self.gateway_retries,
+                        )
+                        .await;
+                    if let Some(dashboard) = &dashboard {
+                        dashboard.set_priority_fee(attempt.get().priority_fee);
+                        let (signature, outcome, earned) = match &result {
+                            Ok(sig) if attempt.get().reset => (
+                                Some(*sig),
+                                "landed, reset epoch".to_string(),
+                                treasury.reward_rate,
+                            ),
+                            Ok(sig) => (Some(*sig), "landed".to_string(), treasury.reward_rate),
+                            Err(SubmitError::Simulated) => {
+                                (None, "simulated, not sent".to_string(), 0)
+                            }
+                            Err(err) => (None, err.cause().to_string(), 0),
+                        };
+                        let submission = Submission {
+                            at: Local::now(),
+                            signature,
+                            outcome,
+                            latency: submitted_at.elapsed(),
+                        };
+                        dashboard.record_submission(submission, earned);
+                    }
+
+                    // A simulated submission never changes the proof, so stop after one.
+                    if self.simulation == Simulation::Only {
+                        match result {
+                            Err(SubmitError::Simulated) => println!("Simulated, not sent"),
+                            Err(err) => println!("Simulation failed: {}", err),
+                            Ok(_) => {}
+                        }
+                        return Ok(());
+                    }
+
+                    match result {
+                        Ok(sig) => {
+                            println!("Success: {}", sig);
+                            metrics().submissions_landed.inc();
+                            metrics().ore_earned.add(reward_rate);
+                            self.send_landed_mine_message();
+                            if attempt.get().reset {
+                                self.send_epoch_reset_message(sig);
+                            }
+                            last_hash = Some(proof.hash);
+                            break;
+                        }
+                        Err(err) => {
+                            metrics()
+                                .submissions_failed
+                                .with_label_values(&[err.cause()])
+                                .inc();
+                            self.send_failed_transaction_message(&err);
+                            match err {
+                                // The proof or difficulty moved on, so the hash is worthless now.
+                                SubmitError::Program(OreError::HashInvalid)
+                                | SubmitError::Program(OreError::DifficultyNotSatisfied) => {
+                                    println!("Hash is no longer valid, mining a new one");
+                                    break;
+                                }
+                                // Wait for the epoch to be reset or mining to open.
+                                SubmitError::Program(OreError::NeedsReset)
+                                | SubmitError::Program(OreError::NotStarted) => {
+                                    println!("{}, retrying shortly", err);
+                                    tokio::time::sleep(Duration::from_secs(RETRY_DELAY_SECS)).await;
+                                }
+                                // Fresh bus balances are read on the next attempt.
+                                SubmitError::Program(OreError::BusRewardsInsufficient) => {}
+                                SubmitError::InsufficientBalance => {
+                                    return Err("Too low balance".to_string());
+                                }
+                                _ => {
+                                    tokio::time::sleep(Duration::from_secs(RETRY_DELAY_SECS)).await;
+                                }
+                            }
+                        }
                     }
                 }
             }
-        }
+        };
+        // The dashboard goes with the mining loop, so it is gone once this returns.
+        let (result, ()) = futures::join!(mining, claiming);
+        result
     }
 
     async fn build_instructions(
-        &self, 
+        &self,
         dynamic_config: bool,
-        signer: Arc<Keypair>, 
-        treasury: Treasury, 
-        next_hash: Hash, 
-        nonce: u64
+        watcher: Option<&AccountWatcher>,
+        snapshot: &ChainSnapshot,
+        (next_hash, nonce): (Hash, u64),
+        reset_policy: ResetPolicy,
+        attempt: &Cell<MineAttempt>,
     ) -> Vec<Instruction> {
-        let (bus, priority_fee) = if dynamic_config {
+        // Pick a bus from fresh state, falling back to the state the hash was mined against.
+        let pubkey = self.signer().pubkey();
+        let snapshot = self
+            .get_watched_snapshot(watcher, pubkey, None)
+            .await
+            .unwrap_or_else(|_| snapshot.clone());
+        let reset = reset_policy.should_reset(&snapshot, self.priority_fee);
+        let (bus, priority_fee) = if reset {
+            // The reset refills every bus, so any of them will do.
+            println!("Epoch is overdue, bundling a reset");
+            let bus_id = rand::thread_rng().gen_range(0..snapshot.busses.len());
+            (snapshot.busses[bus_id], self.priority_fee)
+        } else if dynamic_config {
             let dynamic_config = self.get_dynamic_config().await;
-            self.find_bus_and_priority_id(treasury.reward_rate, dynamic_config).await
+            self.find_bus_and_priority_id(&snapshot, dynamic_config)
+        } else {
+            self.default_find_bus_and_priority_id(&snapshot)
+        };
+        attempt.set(MineAttempt {
+            reset,
+            priority_fee,
+        });
+        let cu_limit = if reset {
+            CU_LIMIT_MINE + CU_LIMIT_RESET
         } else {
-            self.default_find_bus_and_priority_id(treasury.reward_rate).await
+            CU_LIMIT_MINE
         };
-        let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(CU_LIMIT_MINE);
+        let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(cu_limit);
         let cu_price_ix = ComputeBudgetInstruction::set_compute_unit_price(priority_fee);
         let ix_mine = ore::instruction::mine(
-            signer.pubkey(),
+            pubkey,
             BUS_ADDRESSES[bus.id as usize],
             next_hash.into(),
             nonce,
         );
-        let instructions = vec![cu_limit_ix, cu_price_ix, ix_mine];
+        let mut instructions = vec![cu_limit_ix, cu_price_ix];
+        if reset {
+            instructions.push(ore::instruction::reset(pubkey));
+        }
+        instructions.push(ix_mine);
         instructions
     }
 
-    async fn find_bus_and_priority_id(&self, reward_rate: u64, dynamic_config: Option<DynamicConfig>) -> (Bus, u64) {
+    /// Picks the first bus suggested by the dynamic config that can pay out, with its
+    /// suggested priority fee, falling back to `default_find_bus_and_priority_id`.
+    pub fn find_bus_and_priority_id(
+        &self,
+        snapshot: &ChainSnapshot,
+        dynamic_config: Option<DynamicConfig>,
+    ) -> (Bus, u64) {
+        let reward_rate = snapshot.treasury.reward_rate;
         if let Some(config) = dynamic_config {
             for suggested_bus in config.busses.iter() {
-                if let Ok(bus) = self.get_bus(suggested_bus.id).await {
+                if let Some(bus) = snapshot.busses.get(suggested_bus.id) {
                     if bus.rewards.gt(&reward_rate.saturating_mul(4)) {
-                        return (bus, suggested_bus.priority_fee);
+                        return (*bus, suggested_bus.priority_fee);
                     }
                 }
             }
         }
-        
-        self.default_find_bus_and_priority_id(reward_rate).await
-    }
 
-    async fn default_find_bus_and_priority_id(&self, reward_rate: u64) -> (Bus, u64) {
-        let mut rng = rand::thread_rng();
-        loop {
-            let bus_id = rng.gen_range(0..BUS_COUNT);
-            if let Ok(bus) = self.get_bus(bus_id).await {
-                if bus.rewards.gt(&reward_rate.saturating_mul(4)) {
-                    return (bus, self.priority_fee);
-                }
-            }
-        }
+        self.default_find_bus_and_priority_id(snapshot)
     }
 
-    fn find_next_hash_par(
-        &self,
-        hash: KeccakHash,
-        difficulty: KeccakHash,
-        threads: u64,
-    ) -> (KeccakHash, u64) {
-        let found_solution = Arc::new(AtomicBool::new(false));
-        let solution = Arc::new(Mutex::<(KeccakHash, u64)>::new((
-            KeccakHash::new_from_array([0; 32]),
-            0,
-        )));
-        let signer = self.signer();
-        let pubkey = signer.pubkey();
-        let thread_handles: Vec<_> = (0..threads)
-            .map(|i| {
-                std::thread::spawn({
-                    let found_solution = found_solution.clone();
-                    let solution = solution.clone();
-                    // let mut stdout = stdout();
-                    move || {
-                        let n = u64::MAX.saturating_div(threads).saturating_mul(i);
-                        let mut next_hash: KeccakHash;
-                        let mut nonce: u64 = n;
-                        loop {
-                            next_hash = hashv(&[
-                                hash.to_bytes().as_slice(),
-                                pubkey.to_bytes().as_slice(),
-                                nonce.to_le_bytes().as_slice(),
-                            ]);
-                            if nonce % 10_000 == 0 {
-                                if found_solution.load(std::sync::atomic::Ordering::Relaxed) {
-                                    return;
-                                }
-                                // if n == 0 {
-                                //     stdout
-                                //         .write_all(
-                                //             format!("\r{}", next_hash.to_string()).as_bytes(),
-                                //         )
-                                //         .ok();
-                                // }
-                            }
-                            if next_hash.le(&difficulty) {
-                                // stdout
-                                //     .write_all(format!("\r{}", next_hash.to_string()).as_bytes())
-                                //     .ok();
-                                found_solution.store(true, std::sync::atomic::Ordering::Relaxed);
-                                let mut w_solution = solution.lock().expect("failed to lock mutex");
-                                *w_solution = (next_hash, nonce);
-                                return;
-                            }
-                            nonce += 1;
-                        }
-                    }
-                })
-            })
+    /// Picks a random bus holding at least four rewards, or the fullest one if none do,
+    /// paying the configured priority fee.
+    pub fn default_find_bus_and_priority_id(&self, snapshot: &ChainSnapshot) -> (Bus, u64) {
+        let reward_rate = snapshot.treasury.reward_rate;
+        let eligible: Vec<&Bus> = snapshot
+            .busses
+            .iter()
+            .filter(|bus| bus.rewards.gt(&reward_rate.saturating_mul(4)))
             .collect();
-
-        for thread_handle in thread_handles {
-            thread_handle.join().unwrap();
-        }
-
-        let r_solution = solution.lock().expect("Failed to get lock");
-        *r_solution
-    }
-
-    fn find_next_hash_par_2(
-        &self,
-        hash: KeccakHash,
-        difficulty: KeccakHash,
-        threads: u64,
-    ) -> (KeccakHash, u64) {
-        let found_solution = Arc::new(AtomicBool::new(false));
-        let solution = Arc::new(Mutex::<(KeccakHash, u64)>::new((
-            KeccakHash::new_from_array([0; 32]),
-            0,
-        )));
-        let signer = self.signer();
-        let pubkey = signer.pubkey();
-
-        let thread_numbers: Vec<u64> = (0..threads).collect();
-        
-        thread_numbers.par_iter()
-            .for_each(|&i| {
-                let found_solution = found_solution.clone();
-                let solution = solution.clone();
-                let n = u64::MAX.saturating_div(threads).saturating_mul(i);
-                let mut next_hash: KeccakHash;
-                let mut nonce: u64 = n;
-                loop {
-                    next_hash = hashv(&[
-                        hash.to_bytes().as_slice(),
-                        pubkey.to_bytes().as_slice(),
-                        nonce.to_le_bytes().as_slice(),
-                    ]);
-                    if nonce % 10_000 == 0 {
-                        if found_solution.load(std::sync::atomic::Ordering::Relaxed) {
-                            return;
-                        }
-                    }
-                    if next_hash.le(&difficulty) {
-                        found_solution.store(true, std::sync::atomic::Ordering::Relaxed);
-                        let mut w_solution = solution.lock().expect("failed to lock mutex");
-                        *w_solution = (next_hash, nonce);
-                        return;
-                    }
-                    nonce += 1;
-                }
-            });
-
-        let r_solution = solution.lock().expect("Failed to get lock");
-        *r_solution
-    }
-
-    pub async fn get_ore_display_balance(&self) -> String {
-        let client =
-            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
-        let signer = self.signer();
-        let token_account_address = spl_associated_token_account::get_associated_token_address(
-            &signer.pubkey(),
-            &ore::MINT_ADDRESS,
-        );
-        match client.get_token_account(&token_account_address).await {
-            Ok(token_account) => {
-                if let Some(token_account) = token_account {
-                    token_account.token_amount.ui_amount_string
-                } else {
-                    "0.00".to_string()
-                }
-            }
-            Err(_) => "Err".to_string(),
-        }
+        let bus = if eligible.is_empty() {
+            // Every bus is drained until the next reset, so go for the fullest one.
+            snapshot
+                .busses
+                .iter()
+                .max_by_key(|bus| bus.rewards)
+                .unwrap()
+        } else {
+            eligible[rand::thread_rng().gen_range(0..eligible.len())]
+        };
+        (*bus, self.priority_fee)
     }
 
     fn send_started_message(&self) {
-        let message = StartedMessage::new();
-        let json = serde_json::to_string(&message).unwrap();
-        println!("{}", json);
+        emit(&StartedMessage::new());
     }
 
     fn send_landed_mine_message(&self) {
-        let message = LandedMineMessage::new();
-        let json = serde_json::to_string(&message).unwrap();
-        println!("{}", json);
+        emit(&LandedMineMessage::new());
     }
 
-    fn send_failed_transaction_message(&self, err: ClientError) {
-        let message = FailedTransactionMessage::new(err);
-        let json = serde_json::to_string(&message).unwrap();
-        println!("{}", json);
+    fn send_epoch_reset_message(&self, signature: Signature) {
+        emit(&EpochResetMessage::new(signature));
     }
-}
 
-#[derive(Serialize, Deserialize)]
-struct StartedMessage {
-    msg_type: String
+    fn send_failed_transaction_message(&self, err: &SubmitError) {
+        emit(&FailedTransactionMessage::new(err));
+    }
 }
 
-impl StartedMessage {
-    fn new() -> Self {
-        Self {
-            msg_type: "started".to_string()
+#[cfg(test)]
+mod tests {
+    use ore::{EPOCH_DURATION, START_AT};
+
+    use super::*;
+    use crate::{mock_client::MockChainClient, reset::Fleet};
+
+    async fn instructions(
+        miner: &Miner,
+        fleet: Fleet,
+        ore_price: Option<f64>,
+    ) -> (Vec<Instruction>, MineAttempt) {
+        let snapshot = miner
+            .get_snapshot(Some(miner.signer().pubkey()))
+            .await
+            .unwrap();
+        let attempt = Cell::new(MineAttempt::default());
+        let ixs = miner
+            .build_instructions(
+                false,
+                None,
+                &snapshot,
+                (Hash::default(), 0),
+                ResetPolicy { fleet, ore_price },
+                &attempt,
+            )
+            .await;
+        (ixs, attempt.get())
+    }
+
+    // The members of a fleet of `size` that would reset an epoch overdue for `overdue`.
+    async fn resetting_members(size: u64, overdue: i64) -> Vec<u64> {
+        let (_client, miner) = MockChainClient::mining(EPOCH_DURATION + overdue);
+        let mut members = vec![];
+        for index in 0..size {
+            if instructions(&miner, Fleet { size, index }, None)
+                .await
+                .1
+                .reset
+            {
+                members.push(index);
+            }
         }
+        members
     }
-}
 
-#[derive(Serialize, Deserialize)]
-struct LandedMineMessage {
-    msg_type: String,
-}
+    const SOLO: Fleet = Fleet { size: 1, index: 0 };
 
-impl LandedMineMessage {
-    fn new() -> Self {
-        Self {
-            msg_type: "landed_mine_transaction".to_string(),
-        }
+    #[tokio::test]
+    async fn mines_within_epoch() {
+        let (_client, miner) = MockChainClient::mining(10);
+        let (ixs, attempt) = instructions(&miner, SOLO, None).await;
+        assert!(!attempt.reset);
+        assert_eq!(ixs.len(), 3);
+        assert_eq!(ixs[2].program_id, ore::ID);
     }
-}
 
-#[derive(Serialize, Deserialize)]
-struct FailedTransactionMessage {
-    msg_type: String,
-    error: String
-}
+    #[tokio::test]
+    async fn bundles_reset_of_overdue_epoch() {
+        let (_client, miner) = MockChainClient::mining(EPOCH_DURATION + 1);
+        let (ixs, attempt) = instructions(&miner, SOLO, None).await;
+        assert!(attempt.reset);
+        assert_eq!(ixs.len(), 4);
+        assert_eq!(ixs[2], ore::instruction::reset(miner.signer().pubkey()));
+    }
 
-impl FailedTransactionMessage {
-    fn new(error: ClientError) -> Self {
-        Self {
-            msg_type: "failed_transaction".to_string(),
-            error: error.to_string()
-        }
+    #[tokio::test]
+    async fn leaves_reset_to_elected_member() {
+        assert_eq!(resetting_members(8, 1).await.len(), 1);
+
+        // The duty passes on to the next member once the grace period is over.
+        let members = |overdue| -> Vec<u64> {
+            (0..8)
+                .filter(|index| {
+                    Fleet {
+                        size: 8,
+                        index: *index,
+                    }
+                    .elected(START_AT, overdue)
+                })
+                .collect()
+        };
+        let elected = members(1);
+        assert_eq!(elected.len(), 1);
+        assert_eq!(members(21), vec![(elected[0] + 1) % 8]);
     }
-}
 
-#[derive(Serialize, Deserialize)]
-struct SearchHadhStart {
-    msg_type: String,
-    error: String
-}
+    #[tokio::test]
+    async fn skips_unprofitable_reset() {
+        let (_client, miner) = MockChainClient::mining(EPOCH_DURATION + 1);
+        let miner = Miner {
+            priority_fee: 1_000_000,
+            ..miner
+        };
+        // A reward of 1e-6 ORE is worth 100 lamports at 0.1 SOL, against 12,200 in fees.
+        let (ixs, attempt) = instructions(&miner, SOLO, Some(0.1)).await;
+        assert!(!attempt.reset);
+        assert_eq!(ixs.len(), 3);
+        let (_, attempt) = instructions(&miner, SOLO, Some(2_000.0)).await;
+        assert!(attempt.reset);
+    }
 
-impl SearchHadhStart {
-    fn new(error: ClientError) -> Self {
-        Self {
-            msg_type: "failed_transaction".to_string(),
-            error: error.to_string()
-        }
+    #[tokio::test]
+    async fn simulated_mining_sends_nothing() {
+        let (client, miner) = MockChainClient::mining(10);
+        let miner = Miner {
+            simulation: Simulation::Only,
+            ..miner
+        };
+        assert_eq!(miner.mine(MineConfig::default()).await, 0);
+        assert!(client.sent_transactions().is_empty());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn stops_mining_without_fees() {
+        let (client, miner) = MockChainClient::mining(10);
+        client.set_balance(miner.signer().pubkey(), MIN_SOL_BALANCE);
+        assert_eq!(miner.mine(MineConfig::default()).await, 1);
+        assert!(client.sent_transactions().is_empty());
+    }
+}
@@ -0,0 +1,519 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+#[cfg(test)]
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[cfg(test)]
+use ore::{state::Hash as OreHash, START_AT};
+#[cfg(test)]
+use solana_sdk::signature::{write_keypair_file, Keypair};
+
+use async_trait::async_trait;
+use ore::{
+    state::{Bus, Proof, Treasury},
+    utils::{AccountDiscriminator, Discriminator},
+    BUS_ADDRESSES, MINT_ADDRESS, TOKEN_DECIMALS, TREASURY_ADDRESS,
+};
+use solana_account_decoder::parse_token::{parse_token, TokenAccountType, UiTokenAccount};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
+    rpc_request::{RpcError, MAX_MULTIPLE_ACCOUNTS},
+    rpc_response::{Response as RpcResponse, RpcResponseContext, RpcSimulateTransactionResult},
+};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    hash::Hash,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::Signature,
+    system_program, sysvar,
+    transaction::{Transaction, TransactionError},
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account as TokenAccount, AccountState};
+use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+
+use crate::{chain_client::ChainClient, utils::proof_pubkey};
+
+// Blocks a blockhash stays valid for, as on a live cluster.
+const BLOCKHASH_VALIDITY: u64 = 150;
+
+/// The requests of `ChainClient`, for injecting failures into them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MockRequest {
+    GetAccount,
+    GetAccountData,
+    GetMultipleAccounts,
+    GetTokenAccount,
+    GetBalance,
+    GetLatestBlockhash,
+    GetBlockHeight,
+    SendTransaction,
+    SimulateTransaction,
+    GetSignatureStatuses,
+}
+
+/// What happens to a transaction the first time it is sent.
+#[derive(Clone, Debug)]
+pub enum MockOutcome {
+    /// It executes successfully and is finalized.
+    Land,
+    /// It executes and fails with the error.
+    Fail(TransactionError),
+    /// The node accepts it, but it never lands.
+    Drop,
+    /// It lands successfully, but status lookups only see it after this many of them.
+    LandLate(usize),
+}
+
+// The result of a sent transaction, and how many more status lookups miss it.
+struct MockStatus {
+    result: Option<Result<(), TransactionError>>,
+    hidden_for: usize,
+}
+
+#[derive(Default)]
+struct MockState {
+    accounts: HashMap<Pubkey, Account>,
+    slot: u64,
+    block_height: u64,
+    blocks_per_request: u64,
+    failures: HashMap<MockRequest, VecDeque<ClientError>>,
+    outcomes: VecDeque<MockOutcome>,
+    statuses: HashMap<Signature, MockStatus>,
+    sent: Vec<Transaction>,
+    simulation: Option<RpcSimulateTransactionResult>,
+}
+
+/// An in-memory `ChainClient` for tests. Accounts are read from a map, sent transactions
+/// are recorded instead of executed, and failures and outcomes are scripted up front.
+#[derive(Default)]
+pub struct MockChainClient {
+    state: Mutex<MockState>,
+}
+
+impl MockChainClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_account(&self, pubkey: Pubkey, account: Account) {
+        self.state.lock().unwrap().accounts.insert(pubkey, account);
+    }
+
+    /// Gives `pubkey` a system account holding `lamports`.
+    pub fn set_balance(&self, pubkey: Pubkey, lamports: u64) {
+        self.set_account(
+            pubkey,
+            Account {
+                lamports,
+                owner: system_program::ID,
+                ..Default::default()
+            },
+        );
+    }
+
+    pub fn remove_account(&self, pubkey: &Pubkey) {
+        self.state.lock().unwrap().accounts.remove(pubkey);
+    }
+
+    /// Sets the clock sysvar, the treasury and the busses, as read by snapshots.
+    pub fn set_program_state(&self, clock: &Clock, treasury: &Treasury, busses: &[Bus]) {
+        self.set_account(
+            sysvar::clock::ID,
+            program_account(sysvar::ID, bincode::serialize(clock).unwrap()),
+        );
+        self.set_account(
+            TREASURY_ADDRESS,
+            ore_account(treasury.to_bytes(), Treasury::discriminator()),
+        );
+        for bus in busses.iter() {
+            self.set_account(
+                BUS_ADDRESSES[bus.id as usize],
+                ore_account(bus.to_bytes(), Bus::discriminator()),
+            );
+        }
+    }
+
+    /// Registers `proof` as the proof of its authority.
+    pub fn set_proof(&self, proof: &Proof) {
+        self.set_account(
+            proof_pubkey(proof.authority),
+            ore_account(proof.to_bytes(), Proof::discriminator()),
+        );
+    }
+
+    /// Gives `owner` an ORE token account holding `amount`.
+    pub fn set_token_balance(&self, owner: Pubkey, amount: u64) {
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(
+            TokenAccount {
+                mint: MINT_ADDRESS,
+                owner,
+                amount,
+                state: AccountState::Initialized,
+                ..Default::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        self.set_account(
+            get_associated_token_address(&owner, &MINT_ADDRESS),
+            program_account(spl_token::id(), data),
+        );
+    }
+
+    /// Makes the next `request` fail with `err`. Failures queue up per request.
+    pub fn fail_next(&self, request: MockRequest, err: ClientError) {
+        self.state
+            .lock()
+            .unwrap()
+            .failures
+            .entry(request)
+            .or_default()
+            .push_back(err);
+    }
+
+    /// Sets what happens to the next transaction sent. Transactions land by default.
+    pub fn push_outcome(&self, outcome: MockOutcome) {
+        self.state.lock().unwrap().outcomes.push_back(outcome);
+    }
+
+    /// Advances the block height on every block height request, so blockhashes expire.
+    pub fn advance_blocks_per_request(&self, blocks: u64) {
+        self.state.lock().unwrap().blocks_per_request = blocks;
+    }
+
+    /// Sets the result of simulations. Defaults to success with no logs.
+    pub fn set_simulation(&self, result: RpcSimulateTransactionResult) {
+        self.state.lock().unwrap().simulation = Some(result);
+    }
+
+    /// Every transaction sent so far, rebroadcasts included.
+    pub fn sent_transactions(&self) -> Vec<Transaction> {
+        self.state.lock().unwrap().sent.clone()
+    }
+}
+
+/// A miner served by `client` that confirms by polling without delay and gives up after
+/// two rebroadcasts, so failures play out quickly.
+#[cfg(test)]
+pub(crate) fn test_miner(client: &Arc<MockChainClient>) -> crate::Miner {
+    crate::Miner::builder("http://localhost:8899")
+        .client(client.clone())
+        .websocket_url("")
+        .signer(Box::new(solana_sdk::signature::Keypair::new()))
+        .confirm_retries(1)
+        .confirm_interval(0)
+        .gateway_retries(2)
+        .build()
+}
+
+/// A treasury with a difficulty any hash satisfies, last reset at the start of time.
+#[cfg(test)]
+pub(crate) fn test_treasury() -> Treasury {
+    Treasury {
+        admin: Pubkey::new_unique(),
+        bump: 0,
+        difficulty: OreHash([0xff; 32]),
+        last_reset_at: 0,
+        reward_rate: 1_000,
+        total_claimed_rewards: 0,
+    }
+}
+
+/// The proof of `authority` with `claimable_rewards`, all of them earned by mining.
+#[cfg(test)]
+pub(crate) fn test_proof(authority: Pubkey, claimable_rewards: u64) -> Proof {
+    Proof {
+        authority,
+        claimable_rewards,
+        hash: OreHash([7; 32]),
+        total_hashes: 0,
+        total_rewards: claimable_rewards,
+    }
+}
+
+/// Every bus, each holding `rewards`.
+#[cfg(test)]
+pub(crate) fn test_busses(rewards: u64) -> Vec<Bus> {
+    (0..BUS_ADDRESSES.len() as u64)
+        .map(|id| Bus { id, rewards })
+        .collect()
+}
+
+#[cfg(test)]
+impl MockChainClient {
+    /// A client holding a [`test_miner`] with enough SOL to pay for its transactions.
+    pub(crate) fn funded() -> (Arc<Self>, crate::Miner) {
+        let client = Arc::new(Self::new());
+        let miner = test_miner(&client);
+        client.set_balance(miner.signer().pubkey(), 1_000_000_000);
+        (client, miner)
+    }
+
+    /// A [`funded`](Self::funded) miner with a proof of `claimable_rewards`.
+    pub(crate) fn registered(claimable_rewards: u64) -> (Arc<Self>, crate::Miner) {
+        let (client, miner) = Self::funded();
+        client.set_proof(&test_proof(miner.signer().pubkey(), claimable_rewards));
+        (client, miner)
+    }
+
+    /// A [`funded`](Self::funded) miner with `amount` ORE in its token account.
+    pub(crate) fn holding(amount: u64) -> (Arc<Self>, crate::Miner) {
+        let (client, miner) = Self::funded();
+        client.set_token_balance(miner.signer().pubkey(), amount);
+        (client, miner)
+    }
+
+    /// A [`registered`](Self::registered) miner in an epoch that started `elapsed`
+    /// seconds ago, with rewards left in every bus.
+    pub(crate) fn mining(elapsed: i64) -> (Arc<Self>, crate::Miner) {
+        let (client, miner) = Self::registered(0);
+        let now = START_AT + 1_000;
+        let clock = Clock {
+            unix_timestamp: now,
+            ..Default::default()
+        };
+        let treasury = Treasury {
+            last_reset_at: now - elapsed,
+            ..test_treasury()
+        };
+        client.set_program_state(&clock, &treasury, &test_busses(1_000_000));
+        (client, miner)
+    }
+}
+
+/// A new empty directory in the temp dir, named after `name`.
+#[cfg(test)]
+pub(crate) fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ore-{}-{}", name, Pubkey::new_unique()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes `count` plaintext keypairs to `dir`, named as `ore keys generate` names them.
+#[cfg(test)]
+pub(crate) fn write_test_keypairs(dir: &Path, count: usize) -> Vec<Keypair> {
+    (1..=count)
+        .map(|index| {
+            let keypair = Keypair::new();
+            write_keypair_file(&keypair, dir.join(format!("id_miner_{}.json", index))).unwrap();
+            keypair
+        })
+        .collect()
+}
+
+/// The passphrase of encrypted keypairs in tests. It is set for the whole process, since
+/// the keystore keeps the first passphrase it reads.
+#[cfg(test)]
+pub(crate) fn test_passphrase() -> &'static str {
+    const PASSPHRASE: &str = "correct horse battery staple";
+    std::env::set_var(crate::keystore::PASSPHRASE_ENV_VAR, PASSPHRASE);
+    PASSPHRASE
+}
+
+/// An error as returned for a failed RPC request.
+pub fn rpc_error(message: &str) -> ClientError {
+    ClientError {
+        request: None,
+        kind: ClientErrorKind::RpcError(RpcError::RpcRequestError(message.to_string())),
+    }
+}
+
+impl MockState {
+    fn take_failure(&mut self, request: MockRequest) -> ClientResult<()> {
+        match self.failures.get_mut(&request).and_then(|failures| failures.pop_front()) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    fn response<T>(&self, value: T) -> RpcResponse<T> {
+        RpcResponse {
+            context: RpcResponseContext {
+                slot: self.slot,
+                api_version: None,
+            },
+            value,
+        }
+    }
+}
+
+fn program_account(owner: Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: 1_000_000,
+        data,
+        owner,
+        ..Default::default()
+    }
+}
+
+// Lays out an ORE account as the program does: a discriminator padded to 8 bytes, then
+// the state.
+fn ore_account(state: &[u8], discriminator: AccountDiscriminator) -> Account {
+    let mut data = vec![0; 8];
+    data[0] = discriminator as u8;
+    data.extend_from_slice(state);
+    program_account(ore::ID, data)
+}
+
+fn account_not_found(pubkey: &Pubkey) -> ClientError {
+    ClientError {
+        request: None,
+        kind: ClientErrorKind::RpcError(RpcError::ForUser(format!(
+            "AccountNotFound: pubkey={}",
+            pubkey
+        ))),
+    }
+}
+
+#[async_trait]
+impl ChainClient for MockChainClient {
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account> {
+        let mut state = self.state.lock().unwrap();
+        state.take_failure(MockRequest::GetAccount)?;
+        state
+            .accounts
+            .get(pubkey)
+            .cloned()
+            .ok_or_else(|| account_not_found(pubkey))
+    }
+
+    async fn get_account_data(&self, pubkey: &Pubkey) -> ClientResult<Vec<u8>> {
+        let mut state = self.state.lock().unwrap();
+        state.take_failure(MockRequest::GetAccountData)?;
+        state
+            .accounts
+            .get(pubkey)
+            .map(|account| account.data.clone())
+            .ok_or_else(|| account_not_found(pubkey))
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> ClientResult<RpcResponse<Vec<Option<Account>>>> {
+        let mut state = self.state.lock().unwrap();
+        state.take_failure(MockRequest::GetMultipleAccounts)?;
+        // Nodes refuse requests over the limit, as must the mock for batching to be tested.
+        if pubkeys.len() > MAX_MULTIPLE_ACCOUNTS {
+            return Err(rpc_error(&format!(
+                "Too many inputs provided; max {}",
+                MAX_MULTIPLE_ACCOUNTS
+            )));
+        }
+        let accounts = pubkeys
+            .iter()
+            .map(|pubkey| state.accounts.get(pubkey).cloned())
+            .collect();
+        Ok(state.response(accounts))
+    }
+
+    async fn get_token_account(&self, pubkey: &Pubkey) -> ClientResult<Option<UiTokenAccount>> {
+        let mut state = self.state.lock().unwrap();
+        state.take_failure(MockRequest::GetTokenAccount)?;
+        Ok(state.accounts.get(pubkey).and_then(|account| {
+            match parse_token(&account.data, Some(TOKEN_DECIMALS)) {
+                Ok(TokenAccountType::Account(token_account)) => Some(token_account),
+                _ => None,
+            }
+        }))
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> ClientResult<u64> {
+        let mut state = self.state.lock().unwrap();
+        state.take_failure(MockRequest::GetBalance)?;
+        Ok(state.accounts.get(pubkey).map_or(0, |account| account.lamports))
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<(Hash, u64)> {
+        let mut state = self.state.lock().unwrap();
+        state.take_failure(MockRequest::GetLatestBlockhash)?;
+        let blockhash = Hash::new_unique();
+        Ok((blockhash, state.block_height + BLOCKHASH_VALIDITY))
+    }
+
+    async fn get_block_height(&self) -> ClientResult<u64> {
+        let mut state = self.state.lock().unwrap();
+        state.take_failure(MockRequest::GetBlockHeight)?;
+        state.block_height += state.blocks_per_request;
+        state.slot += state.blocks_per_request;
+        Ok(state.block_height)
+    }
+
+    async fn send_transaction(
+        &self,
+        transaction: &Transaction,
+        _config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature> {
+        let mut state = self.state.lock().unwrap();
+        state.take_failure(MockRequest::SendTransaction)?;
+        let signature = transaction.signatures[0];
+        state.sent.push(transaction.clone());
+        // Rebroadcasts keep the outcome of the first send.
+        let state = &mut *state;
+        state.statuses.entry(signature).or_insert_with(|| {
+            let (result, hidden_for) = match state.outcomes.pop_front().unwrap_or(MockOutcome::Land) {
+                MockOutcome::Land => (Some(Ok(())), 0),
+                MockOutcome::Fail(err) => (Some(Err(err)), 0),
+                MockOutcome::Drop => (None, 0),
+                MockOutcome::LandLate(lookups) => (Some(Ok(())), lookups),
+            };
+            MockStatus { result, hidden_for }
+        });
+        Ok(signature)
+    }
+
+    async fn simulate_transaction(
+        &self,
+        _transaction: &Transaction,
+        _config: RpcSimulateTransactionConfig,
+    ) -> ClientResult<RpcResponse<RpcSimulateTransactionResult>> {
+        let mut state = self.state.lock().unwrap();
+        state.take_failure(MockRequest::SimulateTransaction)?;
+        let result = state.simulation.clone().unwrap_or(RpcSimulateTransactionResult {
+            err: None,
+            logs: Some(vec![]),
+            accounts: None,
+            units_consumed: Some(0),
+            return_data: None,
+            inner_instructions: None,
+        });
+        Ok(state.response(result))
+    }
+
+    async fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> ClientResult<RpcResponse<Vec<Option<TransactionStatus>>>> {
+        let mut state = self.state.lock().unwrap();
+        state.take_failure(MockRequest::GetSignatureStatuses)?;
+        let slot = state.slot;
+        let statuses = signatures
+            .iter()
+            .map(|signature| {
+                let status = state.statuses.get_mut(signature)?;
+                if status.hidden_for > 0 {
+                    status.hidden_for -= 1;
+                    return None;
+                }
+                status.result.clone().map(|result| TransactionStatus {
+                    slot,
+                    confirmations: None,
+                    err: result.clone().err(),
+                    status: result,
+                    confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+                })
+            })
+            .collect();
+        Ok(state.response(statuses))
+    }
+}
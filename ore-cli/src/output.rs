@@ -0,0 +1,158 @@
+use std::fmt;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use solana_client::client_error::ClientError;
+
+/// How query commands print their results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable lines.
+    #[default]
+    Plain,
+    /// Aligned columns with a header row.
+    Table,
+    /// A JSON document, with errors as `{"error": {"kind", "message"}}` objects.
+    Json,
+}
+
+/// The result of a query command, printable in every output format.
+pub trait QueryOutput: Serialize {
+    fn plain(&self) -> String;
+
+    fn table(&self) -> Table;
+}
+
+/// Why a query command failed.
+#[derive(Debug)]
+pub enum QueryError {
+    /// An argument could not be parsed or read.
+    InvalidInput(String),
+    /// A requested account does not exist.
+    AccountNotFound(String),
+    /// The RPC node could not be reached or refused a request.
+    Rpc(ClientError),
+}
+
+impl QueryError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::InvalidInput(_) => "invalid_input",
+            Self::AccountNotFound(_) => "account_not_found",
+            Self::Rpc(_) => "rpc_error",
+        }
+    }
+
+    /// The process exit code for the error. 2 matches clap's usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::InvalidInput(_) => 2,
+            Self::AccountNotFound(_) => 3,
+            Self::Rpc(_) => 4,
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidInput(message) => write!(f, "{}", message),
+            Self::AccountNotFound(message) => write!(f, "{}", message),
+            Self::Rpc(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl From<ClientError> for QueryError {
+    fn from(err: ClientError) -> Self {
+        Self::Rpc(err)
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorOutput {
+    error: ErrorDetail,
+}
+
+#[derive(Serialize)]
+struct ErrorDetail {
+    kind: &'static str,
+    message: String,
+}
+
+/// Prints the result of a query command and returns the process exit code.
+pub fn print_result<T: QueryOutput>(format: OutputFormat, result: Result<T, QueryError>) -> i32 {
+    match result {
+        Ok(output) => {
+            match format {
+                OutputFormat::Plain => println!("{}", output.plain()),
+                OutputFormat::Table => println!("{}", output.table()),
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&output).unwrap())
+                }
+            }
+            0
+        }
+        Err(err) => {
+            match format {
+                OutputFormat::Json => {
+                    let output = ErrorOutput {
+                        error: ErrorDetail {
+                            kind: err.kind(),
+                            message: err.to_string(),
+                        },
+                    };
+                    println!("{}", serde_json::to_string_pretty(&output).unwrap());
+                }
+                OutputFormat::Plain | OutputFormat::Table => eprintln!("Error: {}", err),
+            }
+            err.exit_code()
+        }
+    }
+}
+
+/// Rows of text printed in columns padded to their widest cell.
+pub struct Table {
+    header: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(header: Vec<&'static str>) -> Self {
+        Self {
+            header,
+            rows: vec![],
+        }
+    }
+
+    pub fn row(mut self, row: Vec<String>) -> Self {
+        self.rows.push(row);
+        self
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut widths: Vec<usize> = self.header.iter().map(|cell| cell.len()).collect();
+        for row in self.rows.iter() {
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        let header = self.header.iter().map(|cell| cell.to_string()).collect();
+        for (i, row) in std::iter::once(&header).chain(self.rows.iter()).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let line: Vec<String> = row
+                .iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            write!(f, "{}", line.join("  ").trim_end())?;
+        }
+        Ok(())
+    }
+}
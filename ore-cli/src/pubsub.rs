@@ -0,0 +1,117 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// How long to wait for the node to acknowledge an unsubscribe.
+const UNSUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// How long to fall back to polling after a failed connection before trying again, so
+// transactions do not each wait on an endpoint that is down.
+const RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Ends a subscription, as returned next to its stream.
+pub type Unsubscribe = Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>;
+
+#[derive(Default)]
+struct ConnectionState {
+    client: Option<Arc<PubsubClient>>,
+    failed_at: Option<Instant>,
+}
+
+/// The websocket connection of a miner, shared by its confirmations and account watcher.
+/// It is opened on first use and reopened only after it fails.
+pub struct PubsubConnection {
+    url: String,
+    state: Mutex<ConnectionState>,
+}
+
+impl PubsubConnection {
+    /// A connection to `url`. An empty one never connects.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            state: Mutex::new(ConnectionState::default()),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the open connection, connecting if there is none. Returns `None` when
+    /// there is no endpoint, or it could not be reached recently.
+    pub async fn get(&self) -> Option<Arc<PubsubClient>> {
+        if self.url.is_empty() {
+            return None;
+        }
+        {
+            let state = self.state.lock().unwrap();
+            if let Some(client) = &state.client {
+                return Some(client.clone());
+            }
+            if state
+                .failed_at
+                .map_or(false, |failed_at| failed_at.elapsed() < RECONNECT_DELAY)
+            {
+                return None;
+            }
+        }
+        let connected = tokio::time::timeout(CONNECT_TIMEOUT, PubsubClient::new(&self.url)).await;
+        let mut state = self.state.lock().unwrap();
+        match connected {
+            Ok(Ok(client)) => {
+                // Another caller may have connected meanwhile, so keep the first one.
+                let client = state.client.get_or_insert_with(|| Arc::new(client)).clone();
+                state.failed_at = None;
+                Some(client)
+            }
+            _ => {
+                println!("Websocket {} unavailable, polling instead", self.url);
+                state.failed_at = Some(Instant::now());
+                None
+            }
+        }
+    }
+
+    /// Drops `client` after a request on it failed, so the next one reconnects.
+    pub fn reset(&self, client: &Arc<PubsubClient>) {
+        let mut state = self.state.lock().unwrap();
+        if state
+            .client
+            .as_ref()
+            .map_or(false, |current| Arc::ptr_eq(current, client))
+        {
+            state.client = None;
+        }
+    }
+}
+
+/// Ends a subscription in the background. Nodes only drop a subscription by themselves
+/// once it has notified, so one for a signature that never lands would stay open.
+pub fn unsubscribe(unsubscribe: Unsubscribe) {
+    tokio::spawn(tokio::time::timeout(UNSUBSCRIBE_TIMEOUT, unsubscribe()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn waits_before_reconnecting_to_a_dead_endpoint() {
+        assert!(PubsubConnection::new("").get().await.is_none());
+
+        // Nothing listens on the port, so connecting fails without a timeout.
+        let connection = PubsubConnection::new("ws://127.0.0.1:1");
+        assert!(connection.get().await.is_none());
+        let failed_at = connection.state.lock().unwrap().failed_at;
+        assert!(failed_at.is_some());
+        assert!(connection.get().await.is_none());
+        assert_eq!(connection.state.lock().unwrap().failed_at, failed_at);
+    }
+}
@@ -1,20 +1,35 @@
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{
-    commitment_config::CommitmentConfig, compute_budget::ComputeBudgetInstruction,
-    signature::Signer,
-};
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
 
-use crate::{cu_limits::CU_LIMIT_REGISTER, utils::proof_pubkey, Miner};
+use crate::{
+    cu_limits::CU_LIMIT_REGISTER, metrics::time_rpc, submit_error::SubmitError,
+    utils::proof_pubkey, Miner,
+};
 
 impl Miner {
-    pub async fn register(&self) {
+    /// Registers the signer's proof account, returning the exit code.
+    pub async fn register(&self) -> i32 {
+        match self.try_register().await {
+            Ok(true) => println!("Registered {}", self.signer().pubkey()),
+            Ok(false) => println!("Already registered"),
+            Err(SubmitError::Simulated) => println!("Registration simulated, not sent"),
+            Err(err) => {
+                eprintln!("Failed to register: {}", err);
+                return 1;
+            }
+        }
+        0
+    }
+
+    /// Registers the signer's proof account, returning whether it had to be created.
+    pub async fn try_register(&self) -> Result<bool, SubmitError> {
         // Return early if miner is already registered
         let signer = self.signer();
         let proof_address = proof_pubkey(signer.pubkey());
-        let client =
-            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
-        if client.get_account(&proof_address).await.is_ok() {
-            return;
+        if time_rpc("getAccountInfo", self.client.get_account(&proof_address))
+            .await
+            .is_ok()
+        {
+            return Ok(false);
         }
 
         // Sign and send transaction.
@@ -29,7 +44,54 @@ impl Miner {
             self.confirm_interval,
             self.gateway_retries
         )
-        .await
-        .expect("Transaction failed");
+        .await?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ore::state::{Hash, Proof};
+
+    use crate::{
+        mock_client::{rpc_error, test_miner, MockChainClient, MockRequest},
+        submit_error::SubmitError,
+    };
+
+    #[tokio::test]
+    async fn registers_new_miner() {
+        let (client, miner) = MockChainClient::funded();
+        assert!(miner.try_register().await.unwrap());
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 1);
+        let ix = sent[0].message.instructions.last().unwrap();
+        assert_eq!(sent[0].message.account_keys[ix.program_id_index as usize], ore::ID);
+    }
+
+    #[tokio::test]
+    async fn skips_registered_miner() {
+        let client = Arc::new(MockChainClient::new());
+        let miner = test_miner(&client);
+        client.set_proof(&Proof {
+            authority: miner.signer().pubkey(),
+            claimable_rewards: 0,
+            hash: Hash([0; 32]),
+            total_hashes: 0,
+            total_rewards: 0,
+        });
+        assert!(!miner.try_register().await.unwrap());
+        assert!(client.sent_transactions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn gives_up_when_sends_keep_failing() {
+        let (client, miner) = MockChainClient::funded();
+        for _ in 0..=miner.gateway_retries {
+            client.fail_next(MockRequest::SendTransaction, rpc_error("rate limited"));
+        }
+        assert!(matches!(miner.try_register().await, Err(SubmitError::MaxRetries)));
+        assert!(client.sent_transactions().is_empty());
     }
 }
@@ -0,0 +1,94 @@
+use ore::{EPOCH_DURATION, TOKEN_DECIMALS};
+use solana_program::hash::hashv;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+
+use crate::{cu_limits::CU_LIMIT_RESET, snapshot::ChainSnapshot};
+
+// Seconds an overdue epoch is left to the elected member before the duty passes to the
+// next one, in case the elected submitter is down.
+const RESET_GRACE_SECONDS: i64 = 20;
+
+/// The position of this miner within a fleet that shares the reset duty.
+#[derive(Clone, Copy, Debug)]
+pub struct Fleet {
+    pub size: u64,
+    pub index: u64,
+}
+
+/// A miner on its own.
+impl Default for Fleet {
+    fn default() -> Self {
+        Self { size: 1, index: 0 }
+    }
+}
+
+impl Fleet {
+    /// Whether this miner is the one to reset an epoch that fell due at `due_at` and has
+    /// been overdue for `overdue` seconds. The due time picks the member, so the whole
+    /// fleet agrees on it as soon as the reset is due, and the duty moves on to the next
+    /// member every `RESET_GRACE_SECONDS` it stays overdue.
+    pub fn elected(&self, due_at: i64, overdue: i64) -> bool {
+        let size = self.size.max(1);
+        let seed = hashv(&[&due_at.to_le_bytes()]).to_bytes();
+        let first = u64::from_le_bytes(seed[..8].try_into().unwrap()) % size;
+        let turn = (overdue.max(0) / RESET_GRACE_SECONDS) as u64;
+        (first + turn % size) % size == self.index
+    }
+}
+
+/// How this miner takes part in resetting overdue epochs.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResetPolicy {
+    pub fleet: Fleet,
+    /// Price of one ORE in SOL, to weigh the fee of a reset against the reward.
+    pub ore_price: Option<f64>,
+}
+
+impl ResetPolicy {
+    /// Whether the next mine transaction, paying `priority_fee`, should carry a reset.
+    pub fn should_reset(&self, snapshot: &ChainSnapshot, priority_fee: u64) -> bool {
+        should_reset(snapshot, self.fleet)
+            && reset_profitable(snapshot.treasury.reward_rate, priority_fee, self.ore_price)
+    }
+}
+
+/// Returns how many seconds ago the epoch should have been reset, or `None` while it
+/// is still running.
+pub fn epoch_overdue_by(snapshot: &ChainSnapshot) -> Option<i64> {
+    let threshold = epoch_due_at(snapshot);
+    if snapshot.clock.unix_timestamp.ge(&threshold) {
+        Some(snapshot.clock.unix_timestamp.saturating_sub(threshold))
+    } else {
+        None
+    }
+}
+
+fn epoch_due_at(snapshot: &ChainSnapshot) -> i64 {
+    snapshot
+        .treasury
+        .last_reset_at
+        .saturating_add(EPOCH_DURATION)
+}
+
+/// Whether the next mine transaction should carry a reset. Mining fails until an
+/// overdue epoch is reset, so the elected miner bundles it and the rest wait.
+pub fn should_reset(snapshot: &ChainSnapshot, fleet: Fleet) -> bool {
+    match epoch_overdue_by(snapshot) {
+        Some(overdue) => fleet.elected(epoch_due_at(snapshot), overdue),
+        None => false,
+    }
+}
+
+/// Whether a hash earning `reward_rate` pays for the compute units a bundled reset adds
+/// at `priority_fee` microlamports each, with ORE priced at `ore_price` SOL. Without a
+/// price only the fee is known, so a reset is always worth bundling.
+pub fn reset_profitable(reward_rate: u64, priority_fee: u64, ore_price: Option<f64>) -> bool {
+    let Some(ore_price) = ore_price else {
+        return true;
+    };
+    let extra_fee = (priority_fee as f64) * (CU_LIMIT_RESET as f64) / 1_000_000.0;
+    let reward = (reward_rate as f64) / 10f64.powi(TOKEN_DECIMALS as i32)
+        * ore_price
+        * (LAMPORTS_PER_SOL as f64);
+    reward.ge(&extra_fee)
+}
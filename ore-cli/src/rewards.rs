@@ -1,24 +1,46 @@
 use std::str::FromStr;
 
+use serde::Serialize;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::Signer;
 
-use crate::{utils::get_proof, Miner};
+use crate::{
+    output::{QueryError, QueryOutput, Table},
+    utils::format_ore_amount,
+    Miner,
+};
+
+/// The claimable rewards of an address, in ORE base units.
+#[derive(Serialize)]
+pub struct RewardsOutput {
+    pub address: String,
+    pub claimable_rewards: u64,
+}
+
+impl QueryOutput for RewardsOutput {
+    fn plain(&self) -> String {
+        format!("{} ORE", format_ore_amount(self.claimable_rewards))
+    }
+
+    fn table(&self) -> Table {
+        Table::new(vec!["ADDRESS", "CLAIMABLE ORE"])
+            .row(vec![self.address.clone(), format_ore_amount(self.claimable_rewards)])
+    }
+}
 
 impl Miner {
-    pub async fn rewards(&self, address: Option<String>) {
+    pub async fn rewards(&self, address: Option<String>) -> Result<RewardsOutput, QueryError> {
         let address = if let Some(address) = address {
-            if let Ok(address) = Pubkey::from_str(&address) {
-                address
-            } else {
-                println!("Invalid address: {:?}", address);
-                return;
-            }
+            Pubkey::from_str(&address)
+                .map_err(|_| QueryError::InvalidInput(format!("Invalid address: {:?}", address)))?
         } else {
             self.signer().pubkey()
         };
-        let proof = get_proof(self.cluster.clone(), address).await;
-        let amount = (proof.claimable_rewards as f64) / 10f64.powf(ore::TOKEN_DECIMALS as f64);
-        println!("{:} ORE", amount);
+        let proof = self.get_snapshot(Some(address)).await?.proof.ok_or_else(|| {
+            QueryError::AccountNotFound(format!("No proof account found for {}", address))
+        })?;
+        Ok(RewardsOutput {
+            address: address.to_string(),
+            claimable_rewards: proof.claimable_rewards,
+        })
     }
 }
@@ -1,40 +1,44 @@
 use std::{
     io::{stdout, Write},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use solana_client::{
-    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
-    nonblocking::rpc_client::RpcClient,
-    rpc_config::RpcSendTransactionConfig,
+    rpc_config::{RpcSendTransactionConfig, RpcSignatureSubscribeConfig},
+    rpc_response::{Response as RpcResponse, RpcSignatureResult},
 };
 use solana_program::instruction::Instruction;
 use solana_sdk::{
-    commitment_config::{CommitmentConfig, CommitmentLevel},
+    commitment_config::CommitmentLevel,
+    compute_budget::{self, ComputeBudgetInstruction},
     signature::{Signature, Signer},
     transaction::Transaction,
 };
-use solana_transaction_status::{TransactionConfirmationStatus, UiTransactionEncoding};
+use solana_transaction_status::UiTransactionEncoding;
 
-use crate::Miner;
+use crate::{
+    events::{emit, TransactionMessage},
+    metrics::{metrics, time_rpc},
+    pubsub,
+    simulate::Simulation,
+    submit_error::SubmitError,
+    Miner,
+};
 
-use futures::Future;
+use futures::{future, stream::BoxStream, Future, StreamExt};
 
 const RPC_RETRIES: usize = 1;
 
-impl Miner {
-    pub async fn send_and_confirm(
-        &self,
-        ixs: &[Instruction],
-        skip_confirm: bool,
-    ) -> ClientResult<Signature> {
-        const CONFIRM_RETRIES: usize = 3;
-        const GATEWAY_RETRIES: usize = 40;
-        const CONFIRM_INTERVAL: usize = 4;
+/// Lamports below which the signer cannot be expected to cover fees (0.0005 SOL).
+pub const MIN_SOL_BALANCE: u64 = 500_000;
 
-        self.send_and_confirm_2(ixs, skip_confirm, CONFIRM_RETRIES, CONFIRM_INTERVAL, GATEWAY_RETRIES).await
-    }
+// The base fee of each signature.
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+// The compute unit limit of each instruction when a transaction sets none.
+const DEFAULT_INSTRUCTION_CU_LIMIT: u64 = 200_000;
 
+impl Miner {
     pub async fn send_and_confirm_2(
         &self,
         ixs: &[Instruction],
@@ -42,15 +46,26 @@ impl Miner {
         confirm_retries: usize,
         confirm_interval: usize,
         gateway_retries: usize,
-    ) -> ClientResult<Signature> {
+    ) -> Result<Signature, SubmitError> {
+        // Return error if balance is zero
+        let balance = time_rpc(
+            "getBalance",
+            self.client.get_balance(&self.signer().pubkey()),
+        )
+        .await?;
+        if balance <= MIN_SOL_BALANCE {
+            return Err(SubmitError::InsufficientBalance);
+        }
+
         let get_ixs = || async { ixs.to_vec() };
         self.send_and_confirm_3(
-            get_ixs, 
-            skip_confirm, 
-            confirm_retries, 
+            get_ixs,
+            skip_confirm,
+            confirm_retries,
             confirm_interval,
-            gateway_retries
-        ).await
+            gateway_retries,
+        )
+        .await
     }
 
     pub async fn send_and_confirm_3<'a, Fut>(
@@ -60,110 +75,514 @@ impl Miner {
         confirm_retries: usize,
         confirm_interval: usize,
         gateway_retries: usize,
-    ) -> ClientResult<Signature> 
-    where 
+    ) -> Result<Signature, SubmitError>
+    where
+        Fut: Future<Output = Vec<Instruction>>,
+    {
+        self.send_and_confirm_with_signers(
+            get_ixs,
+            &[],
+            skip_confirm,
+            confirm_retries,
+            confirm_interval,
+            gateway_retries,
+        )
+        .await
+    }
+
+    /// Sends instructions that also need the signatures of `extra_signers`. The miner's
+    /// signer remains the fee payer.
+    pub async fn send_and_confirm_with_signers<'a, Fut>(
+        &self,
+        get_ixs: impl Fn() -> Fut,
+        extra_signers: &[&dyn Signer],
+        skip_confirm: bool,
+        confirm_retries: usize,
+        confirm_interval: usize,
+        gateway_retries: usize,
+    ) -> Result<Signature, SubmitError>
+    where
         Fut: Future<Output = Vec<Instruction>>,
     {
         let mut stdout = stdout();
         let signer = self.signer();
-        let client =
-            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
+        let mut signers = vec![signer];
+        signers.extend(
+            extra_signers
+                .iter()
+                .filter(|extra_signer| extra_signer.pubkey() != signer.pubkey()),
+        );
+        // Watch for confirmations over the miner's websocket, falling back to polling alone.
+        let pubsub_client = if skip_confirm || self.simulation == Simulation::Only {
+            None
+        } else {
+            self.pubsub.get().await
+        };
 
-        // Return error if balance is zero
-        let balance = client
-            .get_balance_with_commitment(&signer.pubkey(), CommitmentConfig::confirmed())
-            .await
-            .unwrap();
-        if balance.value <= 500000 { // 0.0005 SOL
-            panic!("Too low balance");
-            // return Err(ClientError {
-            //     request: None,
-            //     kind: ClientErrorKind::Custom("Insufficient SOL balance".into()),
-            // });
-        }
-
-        // Submit tx
-        let mut sigs = vec![];
+        // Sign with a fresh blockhash, then rebroadcast the same transaction until it lands
+        // or the blockhash expires, so only one of the attempts can ever execute. Every
+        // signed transaction is kept, since an earlier one may still land.
         let mut attempts = 0;
+        let mut first_sent_at = None;
+        let mut signed: Vec<Transaction> = vec![];
         loop {
-            println!("Attempt: {:?}", attempts);
-            let (hash, slot) = client
-                .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
-                .await
-                .unwrap(); // TODO add retry on error (timeout happens here sometimes which leads to crash of worker)
+            let (hash, last_valid_block_height) =
+                time_rpc("getLatestBlockhash", self.client.get_latest_blockhash()).await?;
             let send_cfg = RpcSendTransactionConfig {
                 skip_preflight: true,
                 preflight_commitment: Some(CommitmentLevel::Confirmed),
                 encoding: Some(UiTransactionEncoding::Base64),
                 max_retries: Some(RPC_RETRIES),
-                min_context_slot: Some(slot),
+                min_context_slot: None,
             };
-            let ixs = get_ixs().await;
+            let mut ixs = get_ixs().await;
+            if self.simulation != Simulation::Off {
+                ixs = self.simulate_instructions(ixs).await?;
+            }
             let mut tx = Transaction::new_with_payer(&ixs, Some(&signer.pubkey()));
-            tx.sign(&[&signer], hash);
-
-            match client.send_transaction_with_config(&tx, send_cfg).await {
-                Ok(sig) => {
-                    sigs.push(sig);
-                    println!("{:?}", sig);
+            tx.sign(&signers, hash);
+            let sig = tx.signatures[0];
+            if self.simulation == Simulation::Only {
+                println!("Not sent (--simulate-only): {}", sig);
+                return Err(SubmitError::Simulated);
+            }
+            signed.push(tx);
+            let tx = signed.last().unwrap();
 
-                    // Confirm tx
-                    if skip_confirm {
-                        return Ok(sig);
+            // Subscribe before sending so the notification cannot be missed.
+            let (mut subscription, unsubscribe) = match &pubsub_client {
+                Some(pubsub_client) => match pubsub_client
+                    .signature_subscribe(
+                        &sig,
+                        Some(RpcSignatureSubscribeConfig {
+                            commitment: Some(self.commitment),
+                            enable_received_notification: Some(false),
+                        }),
+                    )
+                    .await
+                {
+                    Ok((stream, unsubscribe)) => (Some(stream), Some(unsubscribe)),
+                    Err(_) => {
+                        // The connection is gone, so reconnect for the next transaction.
+                        self.pubsub.reset(pubsub_client);
+                        (None, None)
                     }
-                    for _ in 0..confirm_retries {
-                        let duration_millis = confirm_interval * 1000;
-                        std::thread::sleep(Duration::from_millis(duration_millis as u64));
-                        match client.get_signature_statuses(&sigs).await {
-                            Ok(signature_statuses) => {
-                                println!("Confirms: {:?}", signature_statuses.value);
-                                for signature_status in signature_statuses.value {
-                                    if let Some(signature_status) = signature_status.as_ref() {
-                                        if signature_status.confirmation_status.is_some() {
-                                            let current_commitment = signature_status
-                                                .confirmation_status
-                                                .as_ref()
-                                                .unwrap();
-                                            match current_commitment {
-                                                TransactionConfirmationStatus::Processed => {}
-                                                TransactionConfirmationStatus::Confirmed
-                                                | TransactionConfirmationStatus::Finalized => {
-                                                    println!("Transaction landed!");
-                                                    return Ok(sig);
-                                                }
-                                            }
-                                        } else {
-                                            println!("No status");
-                                        }
-                                    }
-                                }
-                            }
+                },
+                None => (None, None),
+            };
 
-                            // Handle confirmation errors
-                            Err(err) => {
-                                println!("Error: {:?}", err);
+            // Rebroadcast until a transaction lands or fails, or `None` once the blockhash
+            // expired with none of them landed.
+            let outcome = loop {
+                println!("Attempt: {:?}", attempts);
+                first_sent_at.get_or_insert_with(Instant::now);
+                match time_rpc(
+                    "sendTransaction",
+                    self.client.send_transaction(tx, send_cfg),
+                )
+                .await
+                {
+                    Ok(sig) => {
+                        println!("{:?}", sig);
+
+                        // Confirm tx
+                        if skip_confirm {
+                            return Ok(sig);
+                        }
+                        match self
+                            .confirm_signatures(
+                                &signed,
+                                subscription.as_mut(),
+                                confirm_retries,
+                                confirm_interval,
+                            )
+                            .await
+                        {
+                            Some(outcome) => break Some(outcome),
+                            None => {
+                                println!("Transaction did not land");
+                                emit(&TransactionMessage::dropped(sig));
                             }
                         }
                     }
-                    println!("Transaction did not land");
+
+                    // Handle submit errors
+                    Err(err) => {
+                        println!("Error {:?}", err);
+                        emit(&TransactionMessage::rpc_rejected(sig, &err));
+                    }
+                }
+                stdout.flush().ok();
+
+                attempts += 1;
+                if attempts > gateway_retries {
+                    // A transaction may have landed after the last confirmation check.
+                    break Some(
+                        self.signature_outcome(&signed)
+                            .await
+                            .unwrap_or((sig, Err(SubmitError::MaxRetries))),
+                    );
+                }
+
+                // Re-sign only once the blockhash can no longer be used.
+                match time_rpc("getBlockHeight", self.client.get_block_height()).await {
+                    Ok(block_height) if block_height > last_valid_block_height => {
+                        // Nothing may land in between, or a new signature would send twice.
+                        if let Some(outcome) = self.signature_outcome(&signed).await {
+                            break Some(outcome);
+                        }
+                        println!("Blockhash expired");
+                        emit(&TransactionMessage::expired(sig));
+                        break None;
+                    }
+                    Ok(_) => {}
+                    Err(err) => println!("Error: {:?}", err),
+                }
+
+                // Retry
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            };
+            if let Some(unsubscribe) = unsubscribe {
+                pubsub::unsubscribe(unsubscribe);
+            }
+            // Landed transactions pay their fees whether or not they succeeded.
+            if let Some((sig, result)) = &outcome {
+                if !matches!(result, Err(SubmitError::MaxRetries)) {
+                    if let Some(tx) = signed.iter().find(|tx| tx.signatures[0] == *sig) {
+                        metrics().fees_spent.inc_by(transaction_fee(tx));
+                    }
+                }
+            }
+            match outcome {
+                Some((sig, Ok(()))) => {
+                    println!("Transaction landed!");
+                    if let Some(first_sent_at) = first_sent_at {
+                        metrics()
+                            .time_to_land
+                            .observe(first_sent_at.elapsed().as_secs_f64());
+                    }
+                    return Ok(sig);
+                }
+                Some((sig, Err(err))) => {
+                    if !matches!(err, SubmitError::MaxRetries) {
+                        println!("Transaction failed: {}", err);
+                        emit(&TransactionMessage::failed(sig, &err));
+                    }
+                    return Err(err);
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Waits until one of the `signed` transactions reaches the configured commitment,
+    /// returning its signature and execution result, or `None` if none landed in time.
+    /// The signature subscription of the last one returns as soon as it is notified, while
+    /// polling every `confirm_interval` seconds covers a missing websocket and the earlier
+    /// transactions.
+    async fn confirm_signatures(
+        &self,
+        signed: &[Transaction],
+        subscription: Option<&mut BoxStream<'_, RpcResponse<RpcSignatureResult>>>,
+        confirm_retries: usize,
+        confirm_interval: usize,
+    ) -> Option<(Signature, Result<(), SubmitError>)> {
+        let last = signed.last()?;
+        let notified = async {
+            match subscription {
+                Some(stream) => match stream.next().await {
+                    Some(RpcResponse {
+                        value: RpcSignatureResult::ProcessedSignature(result),
+                        ..
+                    }) => Some((
+                        last.signatures[0],
+                        match result.err {
+                            Some(err) => Err(SubmitError::from_transaction_error(last, err)),
+                            None => Ok(()),
+                        },
+                    )),
+                    _ => None,
+                },
+                None => future::pending().await,
+            }
+        };
+        let polled = async {
+            for _ in 0..confirm_retries {
+                tokio::time::sleep(Duration::from_secs(confirm_interval as u64)).await;
+                if let Some(outcome) = self.signature_outcome(signed).await {
+                    return Some(outcome);
                 }
+            }
+            None
+        };
+        tokio::select! {
+            Some(outcome) = notified => Some(outcome),
+            outcome = polled => outcome,
+        }
+    }
 
-                // Handle submit errors
-                Err(err) => {
-                    println!("Error {:?}", err);
+    /// Looks up the `signed` transactions once, returning the first that reached the
+    /// configured commitment with its execution result.
+    async fn signature_outcome(
+        &self,
+        signed: &[Transaction],
+    ) -> Option<(Signature, Result<(), SubmitError>)> {
+        let sigs: Vec<Signature> = signed.iter().map(|tx| tx.signatures[0]).collect();
+        match time_rpc(
+            "getSignatureStatuses",
+            self.client.get_signature_statuses(&sigs),
+        )
+        .await
+        {
+            Ok(signature_statuses) => {
+                println!("Confirms: {:?}", signature_statuses.value);
+                for (tx, signature_status) in signed.iter().zip(signature_statuses.value) {
+                    let Some(signature_status) = signature_status else {
+                        continue;
+                    };
+                    if signature_status.confirmation_status.is_none() {
+                        println!("No status");
+                    } else if signature_status.satisfies_commitment(self.commitment) {
+                        let result = signature_status
+                            .status
+                            .map_err(|err| SubmitError::from_transaction_error(tx, err));
+                        return Some((tx.signatures[0], result));
+                    }
                 }
             }
-            stdout.flush().ok();
-
-            // Retry
-            std::thread::sleep(Duration::from_millis(200));
-            attempts += 1;
-            if attempts > gateway_retries {
-                return Err(ClientError {
-                    request: None,
-                    kind: ClientErrorKind::Custom("Max retries".into()),
-                });
+
+            // Handle confirmation errors
+            Err(err) => {
+                println!("Error: {:?}", err);
+            }
+        }
+        None
+    }
+}
+
+/// The fee `tx` pays once it lands: the base fee of its signatures plus the priority fee
+/// of its compute unit limit.
+fn transaction_fee(tx: &Transaction) -> u64 {
+    let limit_tag = ComputeBudgetInstruction::set_compute_unit_limit(0).data[0];
+    let price_tag = ComputeBudgetInstruction::set_compute_unit_price(0).data[0];
+    let mut cu_limit = None;
+    let mut cu_price = 0;
+    let mut instructions = 0;
+    for ix in tx.message.instructions.iter() {
+        let program_id = tx.message.account_keys[ix.program_id_index as usize];
+        if program_id.ne(&compute_budget::id()) {
+            instructions += 1;
+            continue;
+        }
+        match ix.data.split_first() {
+            Some((tag, data)) if *tag == limit_tag && data.len() >= 4 => {
+                cu_limit = Some(u32::from_le_bytes(data[..4].try_into().unwrap()) as u64);
+            }
+            Some((tag, data)) if *tag == price_tag && data.len() >= 8 => {
+                cu_price = u64::from_le_bytes(data[..8].try_into().unwrap());
             }
+            _ => {}
         }
     }
+    let cu_limit = cu_limit.unwrap_or(instructions * DEFAULT_INSTRUCTION_CU_LIMIT);
+    // The price is in micro-lamports per compute unit, rounded up to whole lamports.
+    let priority_fee = (cu_limit as u128 * cu_price as u128).div_ceil(1_000_000) as u64;
+    LAMPORTS_PER_SIGNATURE * tx.signatures.len() as u64 + priority_fee
+}
+
+#[cfg(test)]
+mod tests {
+    use ore::error::OreError;
+    use solana_sdk::{
+        instruction::InstructionError, pubkey::Pubkey, signature::Keypair,
+        transaction::TransactionError,
+    };
+
+    use super::*;
+    use crate::mock_client::{rpc_error, MockChainClient, MockOutcome, MockRequest};
+
+    fn instructions(miner: &Miner) -> Vec<Instruction> {
+        vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(10_000),
+            ore::instruction::claim(miner.signer().pubkey(), Pubkey::new_unique(), 1),
+        ]
+    }
+
+    async fn send(miner: &Miner) -> Result<Signature, SubmitError> {
+        miner
+            .send_and_confirm_2(
+                &instructions(miner),
+                false,
+                miner.confirm_retries,
+                miner.confirm_interval,
+                miner.gateway_retries,
+            )
+            .await
+    }
+
+    #[test]
+    fn computes_fees_of_compute_budget() {
+        let payer = Keypair::new();
+        let ixs = [
+            ComputeBudgetInstruction::set_compute_unit_limit(10_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_500),
+            ore::instruction::claim(payer.pubkey(), Pubkey::new_unique(), 1),
+        ];
+        let tx = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
+        assert_eq!(transaction_fee(&tx), 5_000 + 15);
+
+        // Without a compute budget, only the base fee is paid.
+        let tx = Transaction::new_with_payer(&ixs[2..], Some(&payer.pubkey()));
+        assert_eq!(transaction_fee(&tx), 5_000);
+    }
+
+    #[tokio::test]
+    async fn lands_on_first_attempt() {
+        let (client, miner) = MockChainClient::funded();
+        let sig = send(&miner).await.unwrap();
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].signatures[0], sig);
+    }
+
+    #[tokio::test]
+    async fn refuses_to_send_without_fees() {
+        let (client, miner) = MockChainClient::funded();
+        client.set_balance(miner.signer().pubkey(), MIN_SOL_BALANCE);
+        assert!(matches!(
+            send(&miner).await,
+            Err(SubmitError::InsufficientBalance)
+        ));
+        assert!(client.sent_transactions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_simulates_when_asked() {
+        let (client, miner) = MockChainClient::funded();
+        let miner = Miner {
+            simulation: Simulation::Only,
+            ..miner
+        };
+        assert!(matches!(send(&miner).await, Err(SubmitError::Simulated)));
+        assert!(client.sent_transactions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn rebroadcasts_after_send_error() {
+        let (client, miner) = MockChainClient::funded();
+        client.fail_next(MockRequest::SendTransaction, rpc_error("node is behind"));
+        let sig = send(&miner).await.unwrap();
+        assert_eq!(client.sent_transactions().len(), 1);
+        assert_eq!(client.sent_transactions()[0].signatures[0], sig);
+    }
+
+    #[tokio::test]
+    async fn rebroadcasts_same_transaction_until_retries_run_out() {
+        let (client, miner) = MockChainClient::funded();
+        client.push_outcome(MockOutcome::Drop);
+        assert!(matches!(send(&miner).await, Err(SubmitError::MaxRetries)));
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), miner.gateway_retries + 1);
+        assert!(sent
+            .iter()
+            .all(|tx| tx.signatures[0] == sent[0].signatures[0]));
+    }
+
+    #[tokio::test]
+    async fn resigns_once_blockhash_expires() {
+        let (client, miner) = MockChainClient::funded();
+        client.push_outcome(MockOutcome::Drop);
+        client.advance_blocks_per_request(200);
+        let sig = send(&miner).await.unwrap();
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 2);
+        assert_ne!(
+            sent[0].message.recent_blockhash,
+            sent[1].message.recent_blockhash
+        );
+        assert_eq!(sent[1].signatures[0], sig);
+    }
+
+    #[tokio::test]
+    async fn finds_transaction_landed_before_expiry() {
+        let (client, miner) = MockChainClient::funded();
+        client.push_outcome(MockOutcome::LandLate(1));
+        client.advance_blocks_per_request(200);
+        let sig = send(&miner).await.unwrap();
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].signatures[0], sig);
+    }
+
+    #[tokio::test]
+    async fn finds_transaction_landed_after_last_check() {
+        let (client, miner) = MockChainClient::funded();
+        client.push_outcome(MockOutcome::LandLate(miner.gateway_retries + 1));
+        let sig = send(&miner).await.unwrap();
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), miner.gateway_retries + 1);
+        assert_eq!(sent[0].signatures[0], sig);
+    }
+
+    #[tokio::test]
+    async fn finds_transaction_of_expired_blockhash() {
+        let (client, miner) = MockChainClient::funded();
+        client.push_outcome(MockOutcome::LandLate(2));
+        client.push_outcome(MockOutcome::Drop);
+        client.advance_blocks_per_request(200);
+        let sig = send(&miner).await.unwrap();
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 2);
+        assert_ne!(sent[0].signatures[0], sent[1].signatures[0]);
+        assert_eq!(sent[0].signatures[0], sig);
+    }
+
+    #[tokio::test]
+    async fn decodes_program_errors() {
+        let (client, miner) = MockChainClient::funded();
+        client.push_outcome(MockOutcome::Fail(TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(u32::from(OreError::ClaimTooLarge)),
+        )));
+        assert!(matches!(
+            send(&miner).await,
+            Err(SubmitError::Program(OreError::ClaimTooLarge))
+        ));
+        assert_eq!(client.sent_transactions().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn keeps_other_execution_errors() {
+        let (client, miner) = MockChainClient::funded();
+        client.push_outcome(MockOutcome::Fail(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(u32::from(OreError::ClaimTooLarge)),
+        )));
+        assert!(matches!(
+            send(&miner).await,
+            Err(SubmitError::Transaction(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn surfaces_blockhash_errors() {
+        let (client, miner) = MockChainClient::funded();
+        client.fail_next(
+            MockRequest::GetLatestBlockhash,
+            rpc_error("connection refused"),
+        );
+        assert!(matches!(send(&miner).await, Err(SubmitError::Client(_))));
+        assert!(client.sent_transactions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn keeps_polling_through_status_errors() {
+        let (client, miner) = MockChainClient::funded();
+        client.fail_next(MockRequest::GetSignatureStatuses, rpc_error("timed out"));
+        let sig = send(&miner).await.unwrap();
+        // The first confirmation check failed, so the transaction was sent again.
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[1].signatures[0], sig);
+    }
 }
@@ -1,69 +1,162 @@
 use std::str::FromStr;
 
-use solana_client::nonblocking::rpc_client::RpcClient;
 use spl_token::state::Account as TokenAccount;
 
 use solana_sdk::{
-  commitment_config::CommitmentConfig, compute_budget::ComputeBudgetInstruction, program_pack::Pack, pubkey::Pubkey, signer::Signer
+  compute_budget::ComputeBudgetInstruction, program_pack::Pack, pubkey::Pubkey
 };
 
-use crate::Miner;
+use crate::{
+  cu_limits::{CU_LIMIT_ATA, CU_LIMIT_COMPUTE_BUDGET, CU_LIMIT_MEMO, CU_LIMIT_TRANSFER},
+  submit_error::SubmitError,
+  utils::{confirm, format_ore_amount},
+  Miner,
+};
 
 impl Miner {
 
-  pub async fn send_ore(&self, recipient_wallet: String) {
+  /// Sends ORE to the token account of `recipient_wallet`, returning the exit code.
+  pub async fn send_ore(&self, recipient_wallet: String, amount: Option<u64>, memo: Option<String>, yes: bool) -> i32 {
     let signer = self.signer();
-    let client = RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
-
-    println!("Sending ORE from {:?} to {}", signer.pubkey(), recipient_wallet);
+    let Ok(receiver_pubkey) = Pubkey::from_str(&recipient_wallet) else {
+      eprintln!("Invalid address: {:?}", recipient_wallet);
+      return 2
+    };
 
     let signer_token_address = spl_associated_token_account::get_associated_token_address(
       &signer.pubkey(),
       &ore::MINT_ADDRESS,
     );
-    let signer_account_data = client.get_account_data(&signer_token_address).await.unwrap();
-    let signer_token_account = TokenAccount::unpack(&signer_account_data).unwrap();
+    let signer_token_account = match self.client.get_account_data(&signer_token_address).await {
+      Ok(data) => match TokenAccount::unpack(&data) {
+        Ok(token_account) => token_account,
+        Err(err) => {
+          eprintln!("Invalid token account {}: {}", signer_token_address, err);
+          return 1
+        }
+      },
+      Err(_) => {
+        eprintln!("No ORE token account found for {}", signer.pubkey());
+        return 1
+      }
+    };
 
-    if signer_token_account.amount == 0 {
-      println!("No ORE to send");
-      return
+    let amount = amount.unwrap_or(signer_token_account.amount);
+    if amount == 0 {
+      eprintln!("No ORE to send");
+      return 1
+    }
+    if amount > signer_token_account.amount {
+      eprintln!(
+        "Insufficient balance: {} ORE requested, {} ORE available",
+        format_ore_amount(amount),
+        format_ore_amount(signer_token_account.amount)
+      );
+      return 1
     }
-
-    let Ok(receiver_pubkey) = Pubkey::from_str(&recipient_wallet) else {
-      println!("Invalid address: {:?}", recipient_wallet);
-      return
-    };
 
     let receiver_token_address = spl_associated_token_account::get_associated_token_address(
       &receiver_pubkey,
       &ore::MINT_ADDRESS,
     );
-    println!("Sender token address: {:?}", signer_token_address);
-    println!("Receiver token address: {:?}", receiver_token_address);
+    println!("Sender:                  {}", signer.pubkey());
+    println!("Sender token address:    {}", signer_token_address);
+    println!("Recipient:               {}", receiver_pubkey);
+    println!("Recipient token address: {}", receiver_token_address);
+    println!("Amount:                  {} ORE", format_ore_amount(amount));
+    if let Some(memo) = &memo {
+      println!("Memo:                    {}", memo);
+    }
+    if !yes && !confirm("Send? [y/N] ") {
+      eprintln!("Aborted");
+      return 1
+    }
 
-    let cu_price_ix = ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee);
-    let transfer_ix = spl_token::instruction::transfer(
+    // The recipient's token account is created in the same transaction if it does not exist yet.
+    let mut cu_limit = CU_LIMIT_COMPUTE_BUDGET + CU_LIMIT_ATA + CU_LIMIT_TRANSFER;
+    let create_ata_ix = spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+      &signer.pubkey(),
+      &receiver_pubkey,
+      &ore::MINT_ADDRESS,
+      &spl_token::id(),
+    );
+    let transfer_ix = spl_token::instruction::transfer_checked(
       &spl_token::id(),
       &signer_token_address,
+      &ore::MINT_ADDRESS,
       &receiver_token_address,
       &signer.pubkey(),
       &[&signer.pubkey()],
-      signer_token_account.amount,
+      amount,
+      ore::TOKEN_DECIMALS,
     ).unwrap();
-    
+    let mut ixs = vec![create_ata_ix, transfer_ix];
+    if let Some(memo) = &memo {
+      ixs.push(spl_memo::build_memo(memo.as_bytes(), &[&signer.pubkey()]));
+      cu_limit += CU_LIMIT_MEMO;
+    }
+    let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(cu_limit);
+    let cu_price_ix = ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee);
+    ixs.insert(0, cu_price_ix);
+    ixs.insert(0, cu_limit_ix);
+
     match self.send_and_confirm_2(
-      &[cu_price_ix, transfer_ix], 
+      &ixs,
       false,
       self.confirm_retries,
       self.confirm_interval,
       self.gateway_retries
     ).await {
-      Ok(_) => {
-        println!("Sent ORE to {}", recipient_wallet);
+      Ok(sig) => {
+        println!("Sent {} ORE to {}: {}", format_ore_amount(amount), recipient_wallet, sig);
+        0
+      }
+      Err(SubmitError::Simulated) => {
+        println!("Sending {} ORE to {} simulated, not sent", format_ore_amount(amount), recipient_wallet);
+        0
       }
       Err(e) => {
-        println!("Failed to send ORE to {}: {:?}", recipient_wallet, e);
+        eprintln!("Failed to send ORE to {}: {}", recipient_wallet, e);
+        1
       }
-    };
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::mock_client::MockChainClient;
+
+  #[tokio::test]
+  async fn sends_requested_amount() {
+    let (client, miner) = MockChainClient::holding(100);
+    let recipient = Pubkey::new_unique();
+    assert_eq!(miner.send_ore(recipient.to_string(), Some(40), Some("rent".to_string()), true).await, 0);
+    let sent = client.sent_transactions();
+    assert_eq!(sent.len(), 1);
+    let program_ids: Vec<Pubkey> = sent[0]
+      .message
+      .instructions
+      .iter()
+      .map(|ix| sent[0].message.account_keys[ix.program_id_index as usize])
+      .collect();
+    assert_eq!(program_ids[2..], [spl_associated_token_account::id(), spl_token::id(), spl_memo::id()]);
+    let transfer = &sent[0].message.instructions[3];
+    assert_eq!(transfer.data[1..9], 40u64.to_le_bytes());
+  }
+
+  #[tokio::test]
+  async fn refuses_more_than_balance() {
+    let (client, miner) = MockChainClient::holding(100);
+    assert_eq!(miner.send_ore(Pubkey::new_unique().to_string(), Some(101), None, true).await, 1);
+    assert!(client.sent_transactions().is_empty());
+  }
+
+  #[tokio::test]
+  async fn needs_token_account() {
+    let (client, miner) = MockChainClient::funded();
+    assert_eq!(miner.send_ore(Pubkey::new_unique().to_string(), None, None, true).await, 1);
+    assert!(client.sent_transactions().is_empty());
   }
-}
\ No newline at end of file
+}
@@ -0,0 +1,132 @@
+use std::{env, error::Error, path::Path};
+
+use clap_v2::ArgMatches;
+use solana_clap_utils::keypair::signer_from_path;
+use solana_cli_config::ConfigInput;
+use solana_sdk::signature::{read_keypair, Keypair, Signer};
+
+use crate::keystore::{self, EncryptedKeypair};
+
+/// Environment variable holding a keypair, either as the JSON byte array of a
+/// keypair file or as a base58 encoded secret key.
+pub const KEYPAIR_ENV_VAR: &str = "ORE_KEYPAIR";
+
+/// Loads the signer from `--keypair`, then `ORE_KEYPAIR`, then the Solana CLI config.
+pub fn load_signer(
+    keypair: Option<&str>,
+    config_keypair_path: &str,
+    passphrase_fd: Option<i32>,
+) -> Result<Box<dyn Signer>, Box<dyn Error>> {
+    if let Some(source) = keypair {
+        return signer_from_source(source, passphrase_fd);
+    }
+    if let Ok(value) = env::var(KEYPAIR_ENV_VAR) {
+        let keypair = keypair_from_str(&value)
+            .map_err(|err| format!("could not parse keypair from {}: {}", KEYPAIR_ENV_VAR, err))?;
+        return Ok(Box::new(keypair));
+    }
+    let (_, source) = ConfigInput::compute_keypair_path_setting("", config_keypair_path);
+    signer_from_source(&source, passphrase_fd)
+}
+
+/// Loads a signer from a keypair filepath or a signer URI, as accepted by the
+/// Solana CLI: `file:`, `stdin:` (or `-`), `prompt:` (or `ASK`) and `usb://ledger`.
+/// Keypair files encrypted with `ore keystore import` are decrypted transparently.
+pub fn signer_from_source(
+    source: &str,
+    passphrase_fd: Option<i32>,
+) -> Result<Box<dyn Signer>, Box<dyn Error>> {
+    let path = source.strip_prefix("file:").unwrap_or(source);
+    if let Some(encrypted) = EncryptedKeypair::read(Path::new(path)) {
+        let keypair = encrypted
+            .decrypt(keystore::passphrase(passphrase_fd)?)
+            .map_err(|err| format!("could not decrypt keypair file \"{}\": {}", path, err))?;
+        return Ok(Box::new(keypair));
+    }
+    let mut wallet_manager = None;
+    signer_from_path(&ArgMatches::default(), source, "keypair", &mut wallet_manager)
+}
+
+fn keypair_from_str(value: &str) -> Result<Keypair, Box<dyn Error>> {
+    let value = value.trim();
+    if value.starts_with('[') {
+        read_keypair(&mut value.as_bytes())
+    } else {
+        let bytes = bs58::decode(value).into_vec()?;
+        Ok(Keypair::from_bytes(&bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use solana_sdk::signature::write_keypair_file;
+
+    use super::*;
+    use crate::mock_client::{test_dir, test_passphrase};
+
+    #[test]
+    fn parses_keypairs_from_env_values() {
+        let keypair = Keypair::new();
+        let json = format!("{:?}\n", keypair.to_bytes().to_vec());
+        assert_eq!(
+            keypair_from_str(&json).unwrap().to_bytes(),
+            keypair.to_bytes()
+        );
+        let base58 = format!(" {} ", keypair.to_base58_string());
+        assert_eq!(
+            keypair_from_str(&base58).unwrap().to_bytes(),
+            keypair.to_bytes()
+        );
+        for value in ["", "[1, 2, 3]", "not a keypair", "3yZe7d"] {
+            assert!(keypair_from_str(value).is_err(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn loads_keypair_files_with_or_without_scheme() {
+        let dir = test_dir("signer");
+        let keypair = Keypair::new();
+        let path = dir.join("id.json");
+        write_keypair_file(&keypair, &path).unwrap();
+        let path = path.to_string_lossy().to_string();
+        for source in [path.clone(), format!("file:{}", path)] {
+            let signer = signer_from_source(&source, None).unwrap();
+            assert_eq!(signer.pubkey(), keypair.pubkey());
+        }
+        assert!(signer_from_source(&dir.join("missing.json").to_string_lossy(), None).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn decrypts_encrypted_keypair_files() {
+        let dir = test_dir("signer");
+        let keypair = Keypair::new();
+        let path = dir.join("id.json");
+        EncryptedKeypair::encrypt(&keypair, test_passphrase())
+            .unwrap()
+            .write(&path)
+            .unwrap();
+        let signer = signer_from_source(&path.to_string_lossy(), None).unwrap();
+        assert_eq!(signer.pubkey(), keypair.pubkey());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn prefers_the_keypair_argument() {
+        let dir = test_dir("signer");
+        let keypair = Keypair::new();
+        let path = dir.join("id.json");
+        write_keypair_file(&keypair, &path).unwrap();
+        let missing = dir.join("missing.json");
+        let signer = load_signer(
+            Some(&path.to_string_lossy()),
+            &missing.to_string_lossy(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(signer.pubkey(), keypair.pubkey());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
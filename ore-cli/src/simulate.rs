@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_program::instruction::Instruction;
+use solana_sdk::{
+    compute_budget::{self, ComputeBudgetInstruction},
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+
+use crate::{metrics::time_rpc, submit_error::SubmitError, Miner};
+
+// Compute units requested while simulating, so the measurement itself cannot run out.
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+// Headroom added on top of the simulated compute units, in percent.
+const CU_SAFETY_MARGIN_PERCENT: u64 = 20;
+
+/// Whether transactions are simulated before they are sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Simulation {
+    /// Send with the compute unit limits of `cu_limits`.
+    Off,
+    /// Simulate each kind of transaction once and size its compute unit limit from it.
+    Measure,
+    /// Simulate every transaction and print the result instead of sending it.
+    Only,
+}
+
+/// Compute unit limits measured by simulation, keyed by the instruction types of a
+/// transaction: the program and first data byte of each non compute budget instruction.
+pub type CuLimitCache = HashMap<Vec<(Pubkey, u8)>, u32>;
+
+impl Miner {
+    /// Replaces the compute unit limit of `ixs` with a measured one, simulating the
+    /// transaction unless instructions of the same types were measured before. With
+    /// `Simulation::Only`, prints the simulation and returns the instructions unchanged.
+    pub async fn simulate_instructions(
+        &self,
+        ixs: Vec<Instruction>,
+    ) -> Result<Vec<Instruction>, SubmitError> {
+        let key = instruction_types(&ixs);
+        if self.simulation == Simulation::Measure {
+            if let Some(cu_limit) = self.cu_limit_cache.lock().unwrap().get(&key) {
+                return Ok(with_cu_limit(ixs, *cu_limit));
+            }
+        }
+
+        // Simulate with the maximum limit, since the estimate may be too low.
+        let tx = Transaction::new_with_payer(
+            &with_cu_limit(ixs.clone(), MAX_COMPUTE_UNIT_LIMIT),
+            Some(&self.signer().pubkey()),
+        );
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(self.commitment),
+            ..Default::default()
+        };
+        let result = time_rpc(
+            "simulateTransaction",
+            self.client.simulate_transaction(&tx, config),
+        )
+        .await?
+        .value;
+        let units_consumed = result.units_consumed.unwrap_or_default();
+
+        if self.simulation == Simulation::Only {
+            println!("Simulated transaction:");
+            for log in result.logs.unwrap_or_default() {
+                println!("  {}", log);
+            }
+            println!("Compute units consumed: {}", units_consumed);
+            if let Some(err) = result.err {
+                return Err(SubmitError::from_transaction_error(&tx, err));
+            }
+            return Ok(ixs);
+        }
+        if let Some(err) = result.err {
+            return Err(SubmitError::from_transaction_error(&tx, err));
+        }
+
+        let cu_limit = units_consumed
+            .saturating_mul(100 + CU_SAFETY_MARGIN_PERCENT)
+            .div_ceil(100)
+            .min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32;
+        println!("Compute units: {} consumed, limit set to {}", units_consumed, cu_limit);
+        self.cu_limit_cache.lock().unwrap().insert(key, cu_limit);
+        Ok(with_cu_limit(ixs, cu_limit))
+    }
+}
+
+fn instruction_types(ixs: &[Instruction]) -> Vec<(Pubkey, u8)> {
+    ixs.iter()
+        .filter(|ix| ix.program_id.ne(&compute_budget::id()))
+        .map(|ix| (ix.program_id, ix.data.first().copied().unwrap_or_default()))
+        .collect()
+}
+
+fn with_cu_limit(mut ixs: Vec<Instruction>, cu_limit: u32) -> Vec<Instruction> {
+    let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(cu_limit);
+    match ixs.iter().position(|ix| {
+        ix.program_id.eq(&compute_budget::id()) && ix.data.first() == cu_limit_ix.data.first()
+    }) {
+        Some(index) => ixs[index] = cu_limit_ix,
+        None => ixs.insert(0, cu_limit_ix),
+    }
+    ixs
+}
+
+#[cfg(test)]
+mod tests {
+    use ore::error::OreError;
+    use solana_client::rpc_response::RpcSimulateTransactionResult;
+    use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+    use super::*;
+    use crate::mock_client::{rpc_error, MockChainClient, MockRequest};
+
+    fn measuring(
+        units_consumed: u64,
+        err: Option<TransactionError>,
+    ) -> (std::sync::Arc<MockChainClient>, Miner) {
+        let (client, miner) = MockChainClient::funded();
+        client.set_simulation(RpcSimulateTransactionResult {
+            err,
+            logs: Some(vec![]),
+            accounts: None,
+            units_consumed: Some(units_consumed),
+            return_data: None,
+            inner_instructions: None,
+        });
+        let miner = Miner {
+            simulation: Simulation::Measure,
+            ..miner
+        };
+        (client, miner)
+    }
+
+    fn ore_ix(kind: u8) -> Instruction {
+        Instruction::new_with_bytes(ore::ID, &[kind], vec![])
+    }
+
+    #[tokio::test]
+    async fn measures_each_kind_of_transaction_once() {
+        let (client, miner) = measuring(100_001, None);
+        let ixs = miner.simulate_instructions(vec![ore_ix(1)]).await.unwrap();
+        let cu_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(120_002);
+        assert_eq!(ixs, vec![cu_limit_ix.clone(), ore_ix(1)]);
+
+        // Measured instructions are not simulated again, whatever their limit was.
+        client.fail_next(
+            MockRequest::SimulateTransaction,
+            rpc_error("node is behind"),
+        );
+        let limited = with_cu_limit(vec![ore_ix(1)], 5_000);
+        let ixs = miner.simulate_instructions(limited).await.unwrap();
+        assert_eq!(ixs, vec![cu_limit_ix, ore_ix(1)]);
+
+        // Other kinds of instructions are.
+        let result = miner.simulate_instructions(vec![ore_ix(2)]).await;
+        assert!(matches!(result, Err(SubmitError::Client(_))));
+    }
+
+    #[tokio::test]
+    async fn caps_limits_at_the_maximum() {
+        let (_client, miner) = measuring(1_300_000, None);
+        let ixs = miner.simulate_instructions(vec![ore_ix(1)]).await.unwrap();
+        assert_eq!(
+            ixs[0],
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT)
+        );
+    }
+
+    #[tokio::test]
+    async fn classifies_failed_simulations() {
+        let err = TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(OreError::NeedsReset.into()),
+        );
+        let (client, miner) = measuring(5_000, Some(err));
+        let result = miner.simulate_instructions(vec![ore_ix(1)]).await;
+        assert!(matches!(
+            result,
+            Err(SubmitError::Program(OreError::NeedsReset))
+        ));
+
+        // Failed simulations are not cached.
+        client.fail_next(
+            MockRequest::SimulateTransaction,
+            rpc_error("node is behind"),
+        );
+        let result = miner.simulate_instructions(vec![ore_ix(1)]).await;
+        assert!(matches!(result, Err(SubmitError::Client(_))));
+    }
+}
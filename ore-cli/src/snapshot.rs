@@ -0,0 +1,171 @@
+use ore::{
+    state::{Bus, Proof, Treasury},
+    utils::AccountDeserialize,
+    BUS_ADDRESSES, BUS_COUNT, MINT_ADDRESS, TREASURY_ADDRESS,
+};
+use solana_client::client_error::{ClientError, ClientErrorKind, Result as ClientResult};
+use solana_program::{program_pack::Pack, pubkey::Pubkey, sysvar};
+use solana_sdk::{account::Account, clock::Clock};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::Account as TokenAccount;
+
+use crate::{
+    metrics::time_rpc,
+    utils::{deserialize_account, proof_pubkey, treasury_tokens_pubkey},
+    Miner,
+};
+
+/// Program and miner state read with a single `getMultipleAccounts` request, so every
+/// value reflects the same slot.
+#[derive(Clone)]
+pub struct ChainSnapshot {
+    pub slot: u64,
+    pub clock: Clock,
+    pub treasury: Treasury,
+    pub treasury_balance: u64,
+    pub busses: Vec<Bus>,
+    /// The proof of the authority, if one was requested and it is registered.
+    pub proof: Option<Proof>,
+    /// ORE held in the authority's associated token account, if it exists.
+    pub token_balance: Option<u64>,
+    /// Lamports held by the authority.
+    pub sol_balance: u64,
+}
+
+impl ChainSnapshot {
+    /// Builds a snapshot from accounts ordered as by `snapshot_addresses`.
+    pub fn from_accounts(
+        slot: u64,
+        accounts: &[Option<Account>],
+        authority: bool,
+    ) -> ClientResult<Self> {
+        let clock = bincode::deserialize::<Clock>(&required(&accounts[0], "clock sysvar")?.data)
+            .map_err(|err| invalid(format!("Failed to deserialize clock: {}", err)))?;
+        let treasury = *Treasury::try_from_bytes(&required(&accounts[1], "treasury")?.data)
+            .map_err(|_| invalid("Failed to parse treasury account".into()))?;
+        let treasury_balance = token_balance(&accounts[2]).unwrap_or(0);
+        let mut busses = Vec::with_capacity(BUS_COUNT);
+        for account in accounts[3..3 + BUS_COUNT].iter() {
+            let bus = *Bus::try_from_bytes(&required(account, "bus")?.data)
+                .map_err(|_| invalid("Failed to parse bus account".into()))?;
+            busses.push(bus);
+        }
+        let (proof, token_balance, sol_balance) = if authority {
+            let rest = &accounts[3 + BUS_COUNT..];
+            let proof = rest[0]
+                .as_ref()
+                .and_then(|account| deserialize_account::<Proof>(&account.data))
+                .copied();
+            let sol_balance = rest[2].as_ref().map_or(0, |account| account.lamports);
+            (proof, token_balance(&rest[1]), sol_balance)
+        } else {
+            (None, None, 0)
+        };
+
+        Ok(Self {
+            slot,
+            clock,
+            treasury,
+            treasury_balance,
+            busses,
+            proof,
+            token_balance,
+            sol_balance,
+        })
+    }
+}
+
+/// Returns the accounts making up a snapshot: the clock, the treasury and its tokens, the
+/// busses and, for an authority, its proof, token account and wallet.
+pub fn snapshot_addresses(authority: Option<Pubkey>) -> Vec<Pubkey> {
+    let mut addresses = vec![sysvar::clock::ID, TREASURY_ADDRESS, treasury_tokens_pubkey()];
+    addresses.extend(BUS_ADDRESSES.iter());
+    if let Some(authority) = authority {
+        addresses.push(proof_pubkey(authority));
+        addresses.push(get_associated_token_address(&authority, &MINT_ADDRESS));
+        addresses.push(authority);
+    }
+    addresses
+}
+
+impl Miner {
+    /// Fetches a snapshot of the program state, plus the proof and balances of
+    /// `authority` when given.
+    pub async fn get_snapshot(&self, authority: Option<Pubkey>) -> ClientResult<ChainSnapshot> {
+        let addresses = snapshot_addresses(authority);
+        let response = time_rpc(
+            "getMultipleAccounts",
+            self.client.get_multiple_accounts(&addresses),
+        )
+        .await?;
+        ChainSnapshot::from_accounts(response.context.slot, &response.value, authority.is_some())
+    }
+}
+
+fn required<'a>(account: &'a Option<Account>, name: &str) -> ClientResult<&'a Account> {
+    account
+        .as_ref()
+        .ok_or_else(|| invalid(format!("Account not found: {}", name)))
+}
+
+fn token_balance(account: &Option<Account>) -> Option<u64> {
+    account
+        .as_ref()
+        .and_then(|account| TokenAccount::unpack(&account.data).ok())
+        .map(|token_account| token_account.amount)
+}
+
+fn invalid(message: String) -> ClientError {
+    ClientError {
+        request: None,
+        kind: ClientErrorKind::Custom(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ore::START_AT;
+
+    use super::*;
+    use crate::mock_client::{test_busses, test_miner, test_treasury, MockChainClient};
+
+    #[tokio::test]
+    async fn reads_program_and_authority_state() {
+        let (client, miner) = MockChainClient::mining(10);
+        let pubkey = miner.signer().pubkey();
+        client.set_token_balance(pubkey, 1_234);
+        let snapshot = miner.get_snapshot(Some(pubkey)).await.unwrap();
+        assert_eq!(snapshot.clock.unix_timestamp, START_AT + 1_000);
+        assert_eq!(snapshot.treasury.last_reset_at, START_AT + 990);
+        assert_eq!(snapshot.busses.len(), BUS_COUNT);
+        assert!(snapshot.busses.iter().all(|bus| bus.rewards == 1_000_000));
+        assert_eq!(snapshot.proof.unwrap().authority, pubkey);
+        assert_eq!(snapshot.token_balance, Some(1_234));
+        assert_eq!(snapshot.sol_balance, 1_000_000_000);
+    }
+
+    #[tokio::test]
+    async fn leaves_out_missing_authority_accounts() {
+        let client = Arc::new(MockChainClient::new());
+        let miner = test_miner(&client);
+        client.set_program_state(&Clock::default(), &test_treasury(), &test_busses(0));
+        let snapshot = miner
+            .get_snapshot(Some(miner.signer().pubkey()))
+            .await
+            .unwrap();
+        assert!(snapshot.proof.is_none());
+        assert_eq!(snapshot.token_balance, None);
+        assert_eq!(snapshot.sol_balance, 0);
+    }
+
+    #[tokio::test]
+    async fn requires_program_state() {
+        let (_client, miner) = MockChainClient::registered(0);
+        assert!(miner
+            .get_snapshot(Some(miner.signer().pubkey()))
+            .await
+            .is_err());
+    }
+}
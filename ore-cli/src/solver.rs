@@ -0,0 +1,173 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use solana_sdk::{
+    keccak::{hashv, Hash as KeccakHash},
+    pubkey::Pubkey,
+};
+
+/// Searches for a nonce whose hash with the current challenge satisfies the difficulty,
+/// splitting the nonce space evenly between threads.
+#[derive(Clone, Debug)]
+pub struct Solver {
+    threads: u64,
+    thread_pool: Option<Arc<ThreadPool>>,
+}
+
+impl Solver {
+    pub fn new(threads: u64) -> Self {
+        Self {
+            threads: threads.max(1),
+            thread_pool: None,
+        }
+    }
+
+    /// Runs the search on a rayon pool of its own, kept across searches, instead of
+    /// spawning dedicated threads for each.
+    pub fn thread_pool(mut self, thread_pool: bool) -> Self {
+        self.thread_pool = thread_pool.then(|| {
+            Arc::new(
+                ThreadPoolBuilder::new()
+                    .num_threads(self.threads as usize)
+                    .build()
+                    .expect("Failed to start the mining thread pool"),
+            )
+        });
+        self
+    }
+
+    /// Blocks until a solution for `hash` is found, returning the next hash and its
+    /// nonce. Every hash computed is added to `hashes`.
+    pub fn solve(
+        &self,
+        pubkey: Pubkey,
+        hash: KeccakHash,
+        difficulty: KeccakHash,
+        hashes: Arc<AtomicU64>,
+    ) -> (KeccakHash, u64) {
+        if let Some(thread_pool) = &self.thread_pool {
+            thread_pool.install(|| self.solve_thread_pool(pubkey, hash, difficulty, hashes))
+        } else {
+            self.solve_threads(pubkey, hash, difficulty, hashes)
+        }
+    }
+
+    fn solve_threads(
+        &self,
+        pubkey: Pubkey,
+        hash: KeccakHash,
+        difficulty: KeccakHash,
+        hashes: Arc<AtomicU64>,
+    ) -> (KeccakHash, u64) {
+        let threads = self.threads;
+        let found_solution = Arc::new(AtomicBool::new(false));
+        let solution = Arc::new(Mutex::<(KeccakHash, u64)>::new((
+            KeccakHash::new_from_array([0; 32]),
+            0,
+        )));
+        let thread_handles: Vec<_> = (0..threads)
+            .map(|i| {
+                std::thread::spawn({
+                    let found_solution = found_solution.clone();
+                    let solution = solution.clone();
+                    let hashes = hashes.clone();
+                    // let mut stdout = stdout();
+                    move || {
+                        let n = u64::MAX.saturating_div(threads).saturating_mul(i);
+                        let mut next_hash: KeccakHash;
+                        let mut nonce: u64 = n;
+                        let mut counted: u64 = n;
+                        loop {
+                            next_hash = hashv(&[
+                                hash.to_bytes().as_slice(),
+                                pubkey.to_bytes().as_slice(),
+                                nonce.to_le_bytes().as_slice(),
+                            ]);
+                            if nonce % 10_000 == 0 {
+                                hashes.fetch_add(nonce - counted, Ordering::Relaxed);
+                                counted = nonce;
+                                if found_solution.load(Ordering::Relaxed) {
+                                    return;
+                                }
+                            }
+                            if next_hash.le(&difficulty) {
+                                // stdout
+                                //     .write_all(format!("\r{}", next_hash.to_string()).as_bytes())
+                                //     .ok();
+                                found_solution.store(true, std::sync::atomic::Ordering::Relaxed);
+                                let mut w_solution = solution.lock().expect("failed to lock mutex");
+                                *w_solution = (next_hash, nonce);
+                                hashes.fetch_add(nonce + 1 - counted, Ordering::Relaxed);
+                                return;
+                            }
+                            nonce += 1;
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for thread_handle in thread_handles {
+            thread_handle.join().unwrap();
+        }
+
+        let r_solution = solution.lock().expect("Failed to get lock");
+        *r_solution
+    }
+
+    fn solve_thread_pool(
+        &self,
+        pubkey: Pubkey,
+        hash: KeccakHash,
+        difficulty: KeccakHash,
+        hashes: Arc<AtomicU64>,
+    ) -> (KeccakHash, u64) {
+        let threads = self.threads;
+        let found_solution = Arc::new(AtomicBool::new(false));
+        let solution = Arc::new(Mutex::<(KeccakHash, u64)>::new((
+            KeccakHash::new_from_array([0; 32]),
+            0,
+        )));
+
+        let thread_numbers: Vec<u64> = (0..threads).collect();
+        
+        thread_numbers.par_iter()
+            .for_each(|&i| {
+                let found_solution = found_solution.clone();
+                let solution = solution.clone();
+                let hashes = hashes.clone();
+                let n = u64::MAX.saturating_div(threads).saturating_mul(i);
+                let mut next_hash: KeccakHash;
+                let mut nonce: u64 = n;
+                let mut counted: u64 = n;
+                loop {
+                    next_hash = hashv(&[
+                        hash.to_bytes().as_slice(),
+                        pubkey.to_bytes().as_slice(),
+                        nonce.to_le_bytes().as_slice(),
+                    ]);
+                    if nonce % 10_000 == 0 {
+                        hashes.fetch_add(nonce - counted, Ordering::Relaxed);
+                        counted = nonce;
+                        if found_solution.load(Ordering::Relaxed) {
+                            return;
+                        }
+                    }
+                    if next_hash.le(&difficulty) {
+                        found_solution.store(true, std::sync::atomic::Ordering::Relaxed);
+                        let mut w_solution = solution.lock().expect("failed to lock mutex");
+                        *w_solution = (next_hash, nonce);
+                        hashes.fetch_add(nonce + 1 - counted, Ordering::Relaxed);
+                        return;
+                    }
+                    nonce += 1;
+                }
+            });
+
+        let r_solution = solution.lock().expect("Failed to get lock");
+        *r_solution
+    }
+}
@@ -0,0 +1,219 @@
+use std::str::FromStr;
+
+use ore::{BUS_COUNT, START_AT};
+use serde::Serialize;
+use solana_client::rpc_request::MAX_MULTIPLE_ACCOUNTS;
+use solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey};
+
+use crate::{
+    keys::{keypair_name, keypair_paths, read_pubkey},
+    metrics::time_rpc,
+    output::{QueryError, QueryOutput, Table},
+    send_and_confirm::MIN_SOL_BALANCE,
+    snapshot::{snapshot_addresses, ChainSnapshot},
+    utils::format_ore_amount,
+    Miner,
+};
+
+// The clock, the treasury and its tokens, and the busses.
+const PROGRAM_ACCOUNTS: usize = 3 + BUS_COUNT;
+
+// Addresses per getMultipleAccounts request, each looking up a proof, a token account
+// and a wallet next to the program accounts.
+const ADDRESSES_PER_REQUEST: usize = (MAX_MULTIPLE_ACCOUNTS - PROGRAM_ACCOUNTS) / 3;
+
+/// Whether an account can mine, with ORE amounts in base units.
+#[derive(Serialize)]
+pub struct AccountStatus {
+    pub name: Option<String>,
+    pub address: String,
+    pub sol_balance: f64,
+    /// `None` when the ORE token account does not exist.
+    pub ore_balance: Option<u64>,
+    pub registered: bool,
+    pub claimable_rewards: u64,
+    pub proof_hash: Option<String>,
+    pub total_hashes: u64,
+    pub total_rewards: u64,
+    pub can_mine: bool,
+    /// Why the account cannot mine right now, if it cannot.
+    pub reason: Option<String>,
+}
+
+impl AccountStatus {
+    fn new(name: Option<String>, address: Pubkey, snapshot: &ChainSnapshot) -> Self {
+        let proof = snapshot.proof.as_ref();
+        let reason = if proof.is_none() {
+            Some("not registered".to_string())
+        } else if snapshot.sol_balance <= MIN_SOL_BALANCE {
+            Some("SOL balance too low".to_string())
+        } else if snapshot.clock.unix_timestamp.lt(&START_AT) {
+            Some("mining has not started".to_string())
+        } else {
+            None
+        };
+        Self {
+            name,
+            address: address.to_string(),
+            sol_balance: lamports_to_sol(snapshot.sol_balance),
+            ore_balance: snapshot.token_balance,
+            registered: proof.is_some(),
+            claimable_rewards: proof.map_or(0, |proof| proof.claimable_rewards),
+            proof_hash: proof.map(|proof| proof.hash.to_string()),
+            total_hashes: proof.map_or(0, |proof| proof.total_hashes),
+            total_rewards: proof.map_or(0, |proof| proof.total_rewards),
+            can_mine: reason.is_none(),
+            reason,
+        }
+    }
+}
+
+impl QueryOutput for Vec<AccountStatus> {
+    /// Several accounts read best as a table, so only a single one is listed line by line.
+    fn plain(&self) -> String {
+        match self.as_slice() {
+            [status] => plain_status(status),
+            _ => self.table().to_string(),
+        }
+    }
+
+    fn table(&self) -> Table {
+        let header = vec![
+            "NAME", "ADDRESS", "SOL", "ORE", "CLAIMABLE", "HASHES", "REWARDS", "REGISTERED",
+            "CAN MINE",
+        ];
+        self.iter().fold(Table::new(header), |table, status| {
+            table.row(vec![
+                status.name.clone().unwrap_or("-".to_string()),
+                status.address.clone(),
+                status.sol_balance.to_string(),
+                status
+                    .ore_balance
+                    .map_or("-".to_string(), format_ore_amount),
+                format_ore_amount(status.claimable_rewards),
+                status.total_hashes.to_string(),
+                format_ore_amount(status.total_rewards),
+                if status.registered { "yes" } else { "no" }.to_string(),
+                status.reason.clone().unwrap_or("yes".to_string()),
+            ])
+        })
+    }
+}
+
+impl Miner {
+    pub async fn status(
+        &self,
+        addresses: Vec<String>,
+        keys_dir: Option<String>,
+    ) -> Result<Vec<AccountStatus>, QueryError> {
+        // Resolve the accounts to report on, defaulting to the signer.
+        let mut accounts: Vec<(Option<String>, Pubkey)> = vec![];
+        for address in addresses.iter() {
+            let pubkey = Pubkey::from_str(address)
+                .map_err(|_| QueryError::InvalidInput(format!("Invalid address: {:?}", address)))?;
+            accounts.push((None, pubkey));
+        }
+        if let Some(dir) = keys_dir {
+            let paths = keypair_paths(&dir)
+                .map_err(|err| QueryError::InvalidInput(format!("Failed to read {}: {}", dir, err)))?;
+            for path in paths.iter() {
+                let pubkey = read_pubkey(path).map_err(|err| {
+                    QueryError::InvalidInput(format!("Failed to load {}: {}", path.display(), err))
+                })?;
+                accounts.push((Some(keypair_name(path)), pubkey));
+            }
+        }
+        if accounts.is_empty() {
+            accounts.push((None, self.signer().pubkey()));
+        }
+
+        // Read the program accounts along with each batch, so every status shares a slot
+        // with the clock it is judged against.
+        let program = snapshot_addresses(None);
+        debug_assert_eq!(program.len(), PROGRAM_ACCOUNTS);
+        let mut statuses = vec![];
+        for batch in accounts.chunks(ADDRESSES_PER_REQUEST) {
+            let mut addresses = program.clone();
+            for (_, pubkey) in batch.iter() {
+                addresses.extend(snapshot_addresses(Some(*pubkey))[program.len()..].iter());
+            }
+            let response = time_rpc(
+                "getMultipleAccounts",
+                self.client.get_multiple_accounts(&addresses),
+            )
+            .await?;
+            let (program_accounts, authority_accounts) = response.value.split_at(program.len());
+            for ((name, pubkey), authority) in batch.iter().zip(authority_accounts.chunks(3)) {
+                let accounts = [program_accounts, authority].concat();
+                let snapshot = ChainSnapshot::from_accounts(response.context.slot, &accounts, true)?;
+                statuses.push(AccountStatus::new(name.clone(), *pubkey, &snapshot));
+            }
+        }
+        Ok(statuses)
+    }
+}
+
+fn plain_status(status: &AccountStatus) -> String {
+    let mut lines = vec![];
+    if let Some(name) = &status.name {
+        lines.push(format!("Name: {}", name));
+    }
+    lines.push(format!("Address: {}", status.address));
+    lines.push(format!("SOL balance: {} SOL", status.sol_balance));
+    match status.ore_balance {
+        Some(balance) => lines.push(format!("ORE balance: {} ORE", format_ore_amount(balance))),
+        None => lines.push("ORE balance: no token account".to_string()),
+    }
+    lines.push(format!("Registered: {}", if status.registered { "yes" } else { "no" }));
+    if let Some(proof_hash) = &status.proof_hash {
+        lines.push(format!("Claimable: {} ORE", format_ore_amount(status.claimable_rewards)));
+        lines.push(format!("Proof hash: {}", proof_hash));
+        lines.push(format!("Total hashes: {}", status.total_hashes));
+        lines.push(format!("Total rewards: {} ORE", format_ore_amount(status.total_rewards)));
+    }
+    match &status.reason {
+        Some(reason) => lines.push(format!("Can mine: no ({})", reason)),
+        None => lines.push("Can mine: yes".to_string()),
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use solana_sdk::clock::Clock;
+
+    use super::*;
+    use crate::mock_client::{test_busses, test_miner, test_treasury, MockChainClient};
+
+    #[tokio::test]
+    async fn batches_many_addresses_within_the_request_limit() {
+        let client = Arc::new(MockChainClient::new());
+        let miner = test_miner(&client);
+        client.set_program_state(&Clock::default(), &test_treasury(), &test_busses(0));
+        let addresses: Vec<String> =
+            (0..40).map(|_| Pubkey::new_unique().to_string()).collect();
+
+        let statuses = miner.status(addresses.clone(), None).await.unwrap();
+        assert_eq!(statuses.len(), addresses.len());
+        for (status, address) in statuses.iter().zip(addresses.iter()) {
+            assert_eq!(&status.address, address);
+            assert!(!status.registered);
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_exact_ore_amounts() {
+        let client = Arc::new(MockChainClient::new());
+        let miner = test_miner(&client);
+        client.set_program_state(&Clock::default(), &test_treasury(), &test_busses(0));
+        let pubkey = miner.signer().pubkey();
+        client.set_token_balance(pubkey, 123_456_789_012_345_678);
+
+        let statuses = miner.status(vec![], None).await.unwrap();
+        let json = serde_json::to_value(&statuses).unwrap();
+        assert_eq!(json[0]["ore_balance"], 123_456_789_012_345_678u64);
+        assert_eq!(statuses.plain().lines().nth(2), Some("ORE balance: 123456789.012345678 ORE"));
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+
+use ore::error::OreError;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_sdk::{
+    instruction::InstructionError,
+    transaction::{Transaction, TransactionError},
+};
+
+/// Why a transaction could not be landed.
+#[derive(Debug)]
+pub enum SubmitError {
+    /// The ORE program rejected an instruction.
+    Program(OreError),
+    /// The transaction executed and failed for another reason.
+    Transaction(TransactionError),
+    /// The signer cannot cover transaction fees.
+    InsufficientBalance,
+    /// No attempt landed before the gateway retries ran out.
+    MaxRetries,
+    /// The RPC node could not be reached or refused a request.
+    Client(ClientError),
+    /// The transaction was only simulated, as `--simulate-only` asks, and never sent.
+    Simulated,
+}
+
+impl SubmitError {
+    /// Classifies the execution error of `tx`, decoding custom errors of ORE instructions.
+    pub fn from_transaction_error(tx: &Transaction, err: TransactionError) -> Self {
+        if let TransactionError::InstructionError(index, InstructionError::Custom(code)) = &err {
+            let is_ore = tx
+                .message
+                .instructions
+                .get(*index as usize)
+                .and_then(|ix| tx.message.account_keys.get(ix.program_id_index as usize))
+                .map_or(false, |program_id| program_id.eq(&ore::ID));
+            if is_ore {
+                if let Some(ore_error) = ore_error(*code) {
+                    return Self::Program(ore_error);
+                }
+            }
+        }
+        Self::Transaction(err)
+    }
+
+    /// A short identifier of the cause, for messages to the orchestrator.
+    pub fn cause(&self) -> &'static str {
+        match self {
+            Self::Program(OreError::NotStarted) => "not_started",
+            Self::Program(OreError::NeedsReset) => "needs_reset",
+            Self::Program(OreError::ResetTooEarly) => "reset_too_early",
+            Self::Program(OreError::HashInvalid) => "hash_invalid",
+            Self::Program(OreError::DifficultyNotSatisfied) => "difficulty_not_satisfied",
+            Self::Program(OreError::BusRewardsInsufficient) => "bus_rewards_insufficient",
+            Self::Program(OreError::ClaimTooLarge) => "claim_too_large",
+            Self::Transaction(_) => "transaction_failed",
+            Self::InsufficientBalance => "insufficient_balance",
+            Self::MaxRetries => "max_retries",
+            Self::Client(_) => "rpc_error",
+            Self::Simulated => "simulated",
+        }
+    }
+}
+
+fn ore_error(code: u32) -> Option<OreError> {
+    [
+        OreError::NotStarted,
+        OreError::NeedsReset,
+        OreError::ResetTooEarly,
+        OreError::HashInvalid,
+        OreError::DifficultyNotSatisfied,
+        OreError::BusRewardsInsufficient,
+        OreError::ClaimTooLarge,
+    ]
+    .into_iter()
+    .find(|err| u32::from(*err) == code)
+}
+
+impl fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Program(err) => write!(f, "{}", err),
+            Self::Transaction(err) => write!(f, "Transaction failed: {}", err),
+            Self::InsufficientBalance => write!(f, "Insufficient SOL balance"),
+            Self::MaxRetries => write!(f, "Max retries"),
+            Self::Client(err) => write!(f, "{}", err),
+            Self::Simulated => write!(f, "Simulated, not sent"),
+        }
+    }
+}
+
+impl std::error::Error for SubmitError {}
+
+impl From<ClientError> for SubmitError {
+    fn from(err: ClientError) -> Self {
+        match err.kind {
+            ClientErrorKind::TransactionError(err) => Self::Transaction(err),
+            _ => Self::Client(err),
+        }
+    }
+}
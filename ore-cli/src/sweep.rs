@@ -0,0 +1,380 @@
+use std::str::FromStr;
+
+use ore::{state::Proof, MINT_ADDRESS, TOKEN_DECIMALS};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::Instruction, packet::PACKET_DATA_SIZE,
+    program_pack::Pack, pubkey::Pubkey, signature::Signer, transaction::Transaction,
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::Account as TokenAccount;
+
+use crate::{
+    cu_limits::{CU_LIMIT_ATA, CU_LIMIT_CLAIM, CU_LIMIT_COMPUTE_BUDGET, CU_LIMIT_TRANSFER},
+    keys::{keypair_name, keypair_paths},
+    signer::signer_from_source,
+    submit_error::SubmitError,
+    utils::{deserialize_account, proof_pubkey},
+    Miner,
+};
+
+// Keypairs per getMultipleAccounts request, each looking up a proof and a token account.
+const KEYS_PER_REQUEST: usize = 50;
+
+struct SweepKey {
+    name: String,
+    signer: Box<dyn Signer>,
+    claim: u64,
+    transfer: u64,
+    /// How the keypair was swept, or `None` until its batch is sent.
+    result: Option<SweepResult>,
+}
+
+#[derive(Clone)]
+enum SweepResult {
+    Swept(String),
+    Nothing,
+    Simulated,
+    Failed(String),
+}
+
+impl SweepKey {
+    fn instructions(&self, destination: &Pubkey) -> (Vec<Instruction>, u32) {
+        let pubkey = self.signer.pubkey();
+        let mut ixs = vec![];
+        let mut cu_limit = 0;
+        if self.claim > 0 {
+            ixs.push(ore::instruction::claim(pubkey, *destination, self.claim));
+            cu_limit += CU_LIMIT_CLAIM;
+        }
+        if self.transfer > 0 {
+            ixs.push(
+                spl_token::instruction::transfer_checked(
+                    &spl_token::id(),
+                    &get_associated_token_address(&pubkey, &MINT_ADDRESS),
+                    &MINT_ADDRESS,
+                    destination,
+                    &pubkey,
+                    &[&pubkey],
+                    self.transfer,
+                    TOKEN_DECIMALS,
+                )
+                .unwrap(),
+            );
+            cu_limit += CU_LIMIT_TRANSFER;
+        }
+        (ixs, cu_limit)
+    }
+}
+
+impl Miner {
+    /// Claims and transfers the ORE of every keypair in `keys_dir` to the token account of
+    /// `to`, returning the exit code. It fails if any keypair could not be swept.
+    pub async fn sweep(
+        &self,
+        keys_dir: String,
+        to: String,
+        min_claimable: u64,
+        passphrase_fd: Option<i32>,
+    ) -> i32 {
+        let Ok(wallet) = Pubkey::from_str(&to) else {
+            eprintln!("Invalid address: {:?}", to);
+            return 2;
+        };
+        let destination = get_associated_token_address(&wallet, &MINT_ADDRESS);
+
+        // Load every keypair.
+        let paths = match keypair_paths(&keys_dir) {
+            Ok(paths) => paths,
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", keys_dir, err);
+                return 1;
+            }
+        };
+        let mut keys = vec![];
+        for path in paths.iter() {
+            match signer_from_source(&path.to_string_lossy(), passphrase_fd) {
+                Ok(signer) => keys.push(SweepKey {
+                    name: keypair_name(path),
+                    signer,
+                    claim: 0,
+                    transfer: 0,
+                    result: None,
+                }),
+                Err(err) => {
+                    eprintln!("Failed to load {}: {}", path.display(), err);
+                    return 1;
+                }
+            }
+        }
+
+        // Look up claimable rewards and token balances.
+        for chunk in keys.chunks_mut(KEYS_PER_REQUEST) {
+            let mut addresses = vec![];
+            for key in chunk.iter() {
+                let pubkey = key.signer.pubkey();
+                addresses.push(proof_pubkey(pubkey));
+                addresses.push(get_associated_token_address(&pubkey, &MINT_ADDRESS));
+            }
+            let accounts = match self.client.get_multiple_accounts(&addresses).await {
+                Ok(response) => response.value,
+                Err(err) => {
+                    eprintln!("Error fetching accounts: {}", err);
+                    return 1;
+                }
+            };
+            for (key, accounts) in chunk.iter_mut().zip(accounts.chunks(2)) {
+                let claimable = accounts[0]
+                    .as_ref()
+                    .and_then(|account| deserialize_account::<Proof>(&account.data))
+                    .map_or(0, |proof| proof.claimable_rewards);
+                if claimable >= min_claimable {
+                    key.claim = claimable;
+                }
+                key.transfer = accounts[1]
+                    .as_ref()
+                    .and_then(|account| TokenAccount::unpack(&account.data).ok())
+                    .map_or(0, |token_account| token_account.amount);
+                if key.claim == 0 && key.transfer == 0 {
+                    key.result = Some(SweepResult::Nothing);
+                }
+            }
+        }
+
+        // Create the destination token account if needed. Every transaction creates it
+        // idempotently, so one failed batch does not fail the ones after it.
+        let create_destination =
+            !matches!(self.client.get_token_account(&destination).await, Ok(Some(_)));
+
+        // Pack as many keypairs into each transaction as fit.
+        let pending: Vec<usize> = (0..keys.len()).filter(|i| keys[*i].result.is_none()).collect();
+        let mut batches: Vec<Vec<usize>> = vec![];
+        let mut batch: Vec<usize> = vec![];
+        for i in pending {
+            batch.push(i);
+            let ixs =
+                self.sweep_instructions(&keys, &batch, &wallet, &destination, create_destination);
+            let tx = Transaction::new_with_payer(&ixs, Some(&self.signer().pubkey()));
+            if batch.len() > 1 && bincode::serialized_size(&tx).unwrap() as usize > PACKET_DATA_SIZE {
+                batch.pop();
+                batches.push(batch);
+                batch = vec![i];
+            }
+        }
+        if !batch.is_empty() {
+            batches.push(batch);
+        }
+
+        // Send each transaction.
+        for batch in batches.iter() {
+            let ixs =
+                self.sweep_instructions(&keys, batch, &wallet, &destination, create_destination);
+            let signers: Vec<&dyn Signer> = batch.iter().map(|i| keys[*i].signer.as_ref()).collect();
+            println!("Sweeping {} keypairs...", batch.len());
+            let result = self
+                .send_and_confirm_with_signers(
+                    || async { ixs.clone() },
+                    &signers,
+                    false,
+                    self.confirm_retries,
+                    self.confirm_interval,
+                    self.gateway_retries,
+                )
+                .await;
+            let result = match result {
+                Ok(sig) => SweepResult::Swept(sig.to_string()),
+                Err(SubmitError::Simulated) => SweepResult::Simulated,
+                Err(err) => {
+                    eprintln!("Failed to sweep {} keypairs: {}", batch.len(), err);
+                    SweepResult::Failed(err.to_string())
+                }
+            };
+            for i in batch.iter() {
+                keys[*i].result = Some(result.clone());
+            }
+        }
+
+        // Report.
+        println!(
+            "\n{:<24} {:<44} {:>14} {:>14} RESULT",
+            "NAME", "PUBKEY", "CLAIMED", "TRANSFERRED"
+        );
+        let mut total = 0;
+        let mut code = 0;
+        for key in keys.iter() {
+            let (claimed, transferred, result) = match &key.result {
+                Some(SweepResult::Swept(sig)) => {
+                    total += key.claim + key.transfer;
+                    (key.claim, key.transfer, sig.clone())
+                }
+                Some(SweepResult::Nothing) => (0, 0, "nothing to sweep".to_string()),
+                Some(SweepResult::Simulated) => (0, 0, "simulated, not sent".to_string()),
+                Some(SweepResult::Failed(err)) => {
+                    code = 1;
+                    (0, 0, err.clone())
+                }
+                None => {
+                    code = 1;
+                    (0, 0, "not sent".to_string())
+                }
+            };
+            println!(
+                "{:<24} {:<44} {:>14} {:>14} {}",
+                key.name,
+                key.signer.pubkey(),
+                (claimed as f64) / 10f64.powf(TOKEN_DECIMALS as f64),
+                (transferred as f64) / 10f64.powf(TOKEN_DECIMALS as f64),
+                result
+            );
+        }
+        println!(
+            "\nSwept {} ORE to {}",
+            (total as f64) / 10f64.powf(TOKEN_DECIMALS as f64),
+            destination
+        );
+        code
+    }
+
+    fn sweep_instructions(
+        &self,
+        keys: &[SweepKey],
+        batch: &[usize],
+        wallet: &Pubkey,
+        destination: &Pubkey,
+        create_destination: bool,
+    ) -> Vec<Instruction> {
+        let mut ixs = vec![];
+        let mut cu_limit = CU_LIMIT_COMPUTE_BUDGET;
+        if create_destination {
+            ixs.push(
+                spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                    &self.signer().pubkey(),
+                    wallet,
+                    &MINT_ADDRESS,
+                    &spl_token::id(),
+                ),
+            );
+            cu_limit += CU_LIMIT_ATA;
+        }
+        for i in batch {
+            let (key_ixs, key_cu_limit) = keys[*i].instructions(destination);
+            ixs.extend(key_ixs);
+            cu_limit += key_cu_limit;
+        }
+        let mut all_ixs = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(cu_limit),
+            ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee),
+        ];
+        all_ixs.extend(ixs);
+        all_ixs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::mock_client::{
+        test_dir, test_proof, write_test_keypairs, MockChainClient, MockOutcome,
+    };
+
+    // The ORE claimed and transferred by each key of `tx`.
+    fn swept(tx: &Transaction) -> Vec<(Pubkey, u64)> {
+        tx.message
+            .instructions
+            .iter()
+            .filter_map(|ix| {
+                let program_id = tx.message.account_keys[ix.program_id_index as usize];
+                let owner = |index: usize| tx.message.account_keys[ix.accounts[index] as usize];
+                if program_id == ore::ID {
+                    Some((
+                        owner(0),
+                        u64::from_le_bytes(ix.data[1..9].try_into().unwrap()),
+                    ))
+                } else if program_id == spl_token::id() {
+                    Some((
+                        owner(3),
+                        u64::from_le_bytes(ix.data[1..9].try_into().unwrap()),
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn sweeps_claimable_rewards_and_balances() {
+        let (client, miner) = MockChainClient::funded();
+        let dir = test_dir("sweep");
+        let keypairs = write_test_keypairs(&dir, 4);
+        client.set_proof(&test_proof(keypairs[0].pubkey(), 5_000));
+        client.set_token_balance(keypairs[0].pubkey(), 700);
+        client.set_token_balance(keypairs[1].pubkey(), 300);
+        // Below the minimum, so only the balance is swept.
+        client.set_proof(&test_proof(keypairs[2].pubkey(), 50));
+        client.set_token_balance(keypairs[2].pubkey(), 0);
+        let wallet = Pubkey::new_unique();
+        let dir_name = dir.to_string_lossy().to_string();
+        assert_eq!(
+            miner.sweep(dir_name, wallet.to_string(), 100, None).await,
+            0
+        );
+
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(
+            swept(&sent[0]),
+            [
+                (keypairs[0].pubkey(), 5_000),
+                (keypairs[0].pubkey(), 700),
+                (keypairs[1].pubkey(), 300),
+            ]
+        );
+        // The destination is created on the way, paid for by the miner.
+        let destination = get_associated_token_address(&wallet, &MINT_ADDRESS);
+        assert!(sent[0].message.account_keys.contains(&destination));
+        assert_eq!(sent[0].message.account_keys[0], miner.signer().pubkey());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn packs_keys_into_transactions_that_fit() {
+        let (client, miner) = MockChainClient::funded();
+        let dir = test_dir("sweep");
+        let keypairs = write_test_keypairs(&dir, 12);
+        for keypair in keypairs.iter() {
+            client.set_proof(&test_proof(keypair.pubkey(), 1_000));
+            client.set_token_balance(keypair.pubkey(), 1_000);
+        }
+        let dir_name = dir.to_string_lossy().to_string();
+        let wallet = Pubkey::new_unique().to_string();
+        assert_eq!(miner.sweep(dir_name, wallet, 0, None).await, 0);
+
+        let sent = client.sent_transactions();
+        assert!(sent.len() > 1);
+        let total: u64 = sent
+            .iter()
+            .flat_map(swept)
+            .map(|(_, amount)| amount)
+            .sum();
+        assert_eq!(total, 24_000);
+        for tx in sent.iter() {
+            assert!(bincode::serialize(tx).unwrap().len() <= PACKET_DATA_SIZE);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn fails_when_a_batch_fails() {
+        let (client, miner) = MockChainClient::funded();
+        let dir = test_dir("sweep");
+        let keypairs = write_test_keypairs(&dir, 1);
+        client.set_token_balance(keypairs[0].pubkey(), 1_000);
+        client.push_outcome(MockOutcome::Drop);
+        let dir_name = dir.to_string_lossy().to_string();
+        let wallet = Pubkey::new_unique().to_string();
+        assert_eq!(miner.sweep(dir_name, wallet, 0, None).await, 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
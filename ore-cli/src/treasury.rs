@@ -1,31 +1,79 @@
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::commitment_config::CommitmentConfig;
+use ore::EPOCH_DURATION;
+use serde::Serialize;
 
 use crate::{
-    utils::{get_treasury, treasury_tokens_pubkey},
+    output::{QueryError, QueryOutput, Table},
+    utils::format_ore_amount,
     Miner,
 };
 
-impl Miner {
-    pub async fn treasury(&self) {
-        let client =
-            RpcClient::new_with_commitment(self.cluster.clone(), CommitmentConfig::confirmed());
-        if let Ok(Some(treasury_tokens)) = client.get_token_account(&treasury_tokens_pubkey()).await
-        {
-            let treasury = get_treasury(self.cluster.clone()).await;
-            let balance = treasury_tokens.token_amount.ui_amount_string;
-            println!("{:} ORE", balance);
-            println!("Admin: {}", treasury.admin);
-            println!("Difficulty: {}", treasury.difficulty.to_string());
-            println!("Last reset at: {}", treasury.last_reset_at);
-            println!(
-                "Reward rate: {} ORE",
-                (treasury.reward_rate as f64) / 10f64.powf(ore::TOKEN_DECIMALS as f64)
-            );
-            println!(
+/// The treasury state, with ORE amounts in base units.
+#[derive(Serialize)]
+pub struct TreasuryOutput {
+    pub balance: u64,
+    pub admin: String,
+    pub difficulty: String,
+    pub last_reset_at: i64,
+    pub next_reset_in: i64,
+    pub reward_rate: u64,
+    pub total_claimed_rewards: u64,
+    pub slot: u64,
+}
+
+impl QueryOutput for TreasuryOutput {
+    fn plain(&self) -> String {
+        [
+            format!("{} ORE", format_ore_amount(self.balance)),
+            format!("Admin: {}", self.admin),
+            format!("Difficulty: {}", self.difficulty),
+            format!("Last reset at: {}", self.last_reset_at),
+            format!("Next reset in: {}s", self.next_reset_in),
+            format!("Reward rate: {} ORE", format_ore_amount(self.reward_rate)),
+            format!(
                 "Total claimed rewards: {} ORE",
-                (treasury.total_claimed_rewards as f64) / 10f64.powf(ore::TOKEN_DECIMALS as f64)
-            );
-        }
+                format_ore_amount(self.total_claimed_rewards)
+            ),
+            format!("Slot: {}", self.slot),
+        ]
+        .join("\n")
+    }
+
+    fn table(&self) -> Table {
+        Table::new(vec!["FIELD", "VALUE"])
+            .row(vec!["Balance".into(), format!("{} ORE", format_ore_amount(self.balance))])
+            .row(vec!["Admin".into(), self.admin.clone()])
+            .row(vec!["Difficulty".into(), self.difficulty.clone()])
+            .row(vec!["Last reset at".into(), self.last_reset_at.to_string()])
+            .row(vec!["Next reset in".into(), format!("{}s", self.next_reset_in)])
+            .row(vec![
+                "Reward rate".into(),
+                format!("{} ORE", format_ore_amount(self.reward_rate)),
+            ])
+            .row(vec![
+                "Total claimed rewards".into(),
+                format!("{} ORE", format_ore_amount(self.total_claimed_rewards)),
+            ])
+            .row(vec!["Slot".into(), self.slot.to_string()])
+    }
+}
+
+impl Miner {
+    pub async fn treasury(&self) -> Result<TreasuryOutput, QueryError> {
+        let snapshot = self.get_snapshot(None).await?;
+        let treasury = snapshot.treasury;
+        Ok(TreasuryOutput {
+            balance: snapshot.treasury_balance,
+            admin: treasury.admin.to_string(),
+            difficulty: treasury.difficulty.to_string(),
+            last_reset_at: treasury.last_reset_at,
+            next_reset_in: treasury
+                .last_reset_at
+                .saturating_add(EPOCH_DURATION)
+                .saturating_sub(snapshot.clock.unix_timestamp)
+                .max(0),
+            reward_rate: treasury.reward_rate,
+            total_claimed_rewards: treasury.total_claimed_rewards,
+            slot: snapshot.slot,
+        })
     }
 }
@@ -1,17 +1,28 @@
 use std::str::FromStr;
 
 use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::Signer;
 
-use crate::Miner;
+use crate::{submit_error::SubmitError, Miner};
 
 impl Miner {
     pub async fn update_admin(&self, new_admin: String) {
         let signer = self.signer();
         let new_admin = Pubkey::from_str(new_admin.as_str()).unwrap();
         let ix = ore::instruction::update_admin(signer.pubkey(), new_admin);
-        self.send_and_confirm(&[ix], false)
+        match self
+            .send_and_confirm_2(
+                &[ix],
+                false,
+                self.confirm_retries,
+                self.confirm_interval,
+                self.gateway_retries,
+            )
             .await
-            .expect("Transaction failed");
+        {
+            Err(SubmitError::Simulated) => println!("Admin update simulated, not sent"),
+            result => {
+                result.expect("Transaction failed");
+            }
+        }
     }
 }
@@ -1,24 +1,301 @@
+use std::fmt::Write;
+
+use ore::{state::Treasury, utils::AccountDeserialize, TREASURY_ADDRESS};
 use solana_program::keccak::Hash as KeccakHash;
-use solana_sdk::signature::Signer;
+use solana_sdk::signature::Signature;
+
+use crate::{submit_error::SubmitError, utils::confirm, Miner};
 
-use crate::Miner;
+/// The difficulty to set, in one of the forms the command accepts.
+#[derive(Clone, Copy, Debug)]
+pub enum DifficultyTarget {
+    /// Hashes must start with this many zero bits.
+    LeadingZeroBits(u32),
+    /// The difficulty itself.
+    Exact(KeccakHash),
+    /// One solution every `seconds` on average, at `hashrate` hashes per second.
+    SolveTime { seconds: f64, hashrate: f64 },
+}
+
+impl DifficultyTarget {
+    pub fn difficulty(&self) -> KeccakHash {
+        match *self {
+            Self::LeadingZeroBits(bits) => difficulty_from_leading_zero_bits(bits),
+            Self::Exact(difficulty) => difficulty,
+            Self::SolveTime { seconds, hashrate } => {
+                difficulty_from_expected_hashes(seconds * hashrate)
+            }
+        }
+    }
+}
 
 impl Miner {
-    pub async fn update_difficulty(&self) {
-        let signer = self.signer();
-        // let new_difficulty = KeccakHash::new_from_array([
-        //     0, 0, 0, 64, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
-        //     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
-        // ]);
-        let new_difficulty = KeccakHash::new_from_array([
-            0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
-        ]);
-        let ix = ore::instruction::update_difficulty(signer.pubkey(), new_difficulty.into());
-        // let bs58data = bs58::encode(ix.data).into_string();
-        // println!("Data: {:?}", bs58data);
-        self.send_and_confirm(&[ix], false)
-            .await
-            .expect("Transaction failed");
+    /// Sets a new difficulty after showing how it compares to the current one. Only the
+    /// treasury admin may, so other signers are turned away before anything is sent.
+    pub async fn update_difficulty(
+        &self,
+        target: DifficultyTarget,
+        hashrate: Option<f64>,
+        yes: bool,
+    ) {
+        let treasury = match self.client.get_account_data(&TREASURY_ADDRESS).await {
+            Ok(data) => *Treasury::try_from_bytes(&data).expect("Failed to parse treasury"),
+            Err(err) => {
+                println!("Error fetching the treasury: {}", err);
+                return;
+            }
+        };
+        let signer = self.signer().pubkey();
+        if treasury.admin != signer {
+            println!(
+                "The signer {} is not the treasury admin {}",
+                signer, treasury.admin
+            );
+            return;
+        }
+
+        let current: KeccakHash = treasury.difficulty.into();
+        let new = target.difficulty();
+        let hashrate = match target {
+            DifficultyTarget::SolveTime { hashrate, .. } => Some(hashrate),
+            _ => hashrate,
+        };
+        println!("Current difficulty: {}", difficulty_hex(&current));
+        println!("                    {}", describe_difficulty(&current, hashrate));
+        println!("New difficulty:     {}", difficulty_hex(&new));
+        println!("                    {}", describe_difficulty(&new, hashrate));
+        if new == current {
+            println!("Difficulty unchanged");
+            return;
+        }
+        if !yes && !confirm("Update the difficulty? [y/N] ") {
+            println!("Aborted");
+            return;
+        }
+
+        match self.try_update_difficulty(new).await {
+            Ok(sig) => println!("Updated the difficulty: {}", sig),
+            Err(SubmitError::Simulated) => println!("Difficulty update simulated, not sent"),
+            Err(err) => println!("Failed to update the difficulty: {}", err),
+        }
+    }
+
+    /// Sets the difficulty hashes must be at or below. Only the treasury admin may.
+    pub async fn try_update_difficulty(
+        &self,
+        difficulty: KeccakHash,
+    ) -> Result<Signature, SubmitError> {
+        let ix = ore::instruction::update_difficulty(self.signer().pubkey(), difficulty.into());
+        self.send_and_confirm_2(
+            &[ix],
+            false,
+            self.confirm_retries,
+            self.confirm_interval,
+            self.gateway_retries,
+        )
+        .await
+    }
+}
+
+/// The difficulty passing hashes that start with `bits` zero bits.
+pub fn difficulty_from_leading_zero_bits(bits: u32) -> KeccakHash {
+    let mut bytes = [255u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        let zeros = bits.saturating_sub(8 * i as u32).min(8);
+        *byte = if zeros == 8 { 0 } else { 255 >> zeros };
+    }
+    KeccakHash::new_from_array(bytes)
+}
+
+/// Parses a difficulty written as 64 hex characters, most significant byte first.
+pub fn parse_difficulty_hex(hex: &str) -> Result<KeccakHash, String> {
+    let digits = hex.strip_prefix("0x").unwrap_or(hex);
+    if digits.len() != 64 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("expected 64 hex characters, got {:?}", hex));
+    }
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).unwrap();
+    }
+    Ok(KeccakHash::new_from_array(bytes))
+}
+
+/// Parses a solve time or hashrate, which has to be a positive finite number.
+pub fn parse_solve_rate(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate > 0.0 => Ok(rate),
+        _ => Err(format!("expected a positive number, got {:?}", value)),
+    }
+}
+
+/// Average number of hashes it takes to find one at or below `difficulty`.
+pub fn expected_hashes(difficulty: &KeccakHash) -> f64 {
+    let value = difficulty
+        .to_bytes()
+        .iter()
+        .fold(0f64, |value, byte| value * 256.0 + *byte as f64);
+    2f64.powi(256) / (value + 1.0)
+}
+
+/// The difficulty finding a hash after `hashes` attempts on average. Only the leading 53
+/// bits are exact, which is well within the noise of mining. Targets past the hardest
+/// difficulty a hash can still meet get that one, never the unsolvable zero.
+fn difficulty_from_expected_hashes(hashes: f64) -> KeccakHash {
+    let mut rest = (2f64.powi(256) / hashes.max(1.0) - 1.0).max(1.0);
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        let scale = 256f64.powi(31 - i as i32);
+        let digit = (rest / scale).floor().min(255.0);
+        *byte = digit as u8;
+        rest -= digit * scale;
+    }
+    KeccakHash::new_from_array(bytes)
+}
+
+fn leading_zero_bits(difficulty: &KeccakHash) -> u32 {
+    let mut bits = 0;
+    for byte in difficulty.to_bytes() {
+        bits += byte.leading_zeros();
+        if byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+fn difficulty_hex(difficulty: &KeccakHash) -> String {
+    difficulty
+        .to_bytes()
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
+
+fn describe_difficulty(difficulty: &KeccakHash, hashrate: Option<f64>) -> String {
+    let hashes = expected_hashes(difficulty);
+    let mut description = format!(
+        "{} leading zero bits, {:.3e} hashes per solution",
+        leading_zero_bits(difficulty),
+        hashes
+    );
+    match hashrate {
+        Some(hashrate) if hashrate > 0.0 => description.push_str(&format!(
+            ", about {} at {} H/s",
+            format_duration(hashes / hashrate),
+            hashrate
+        )),
+        _ => description.push_str(" (pass --hashrate for a solve time)"),
+    }
+    description
+}
+
+fn format_duration(seconds: f64) -> String {
+    if seconds < 120.0 {
+        format!("{:.1}s", seconds)
+    } else if seconds < 7_200.0 {
+        format!("{:.1}m", seconds / 60.0)
+    } else if seconds < 172_800.0 {
+        format!("{:.1}h", seconds / 3_600.0)
+    } else {
+        format!("{:.1}d", seconds / 86_400.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ore::state::Treasury;
+    use solana_sdk::{clock::Clock, pubkey::Pubkey};
+
+    use super::*;
+    use crate::mock_client::{test_busses, test_miner, test_treasury, MockChainClient};
+
+    fn set_treasury(client: &MockChainClient, admin: Pubkey) {
+        let treasury = Treasury {
+            admin,
+            difficulty: difficulty_from_leading_zero_bits(8).into(),
+            ..test_treasury()
+        };
+        client.set_program_state(&Clock::default(), &treasury, &test_busses(0));
+    }
+
+    #[test]
+    fn leading_zero_bits_round_trip() {
+        for bits in [0, 3, 8, 13, 255, 256] {
+            let difficulty = difficulty_from_leading_zero_bits(bits);
+            assert_eq!(leading_zero_bits(&difficulty), bits);
+        }
+        let mut eight = [255u8; 32];
+        eight[0] = 0;
+        assert_eq!(difficulty_from_leading_zero_bits(8).to_bytes(), eight);
+        assert_eq!(expected_hashes(&difficulty_from_leading_zero_bits(8)), 256.0);
+    }
+
+    #[test]
+    fn parses_hex_difficulty() {
+        let hex = format!("0x00{}", "ff".repeat(31));
+        assert_eq!(
+            parse_difficulty_hex(&hex).unwrap(),
+            difficulty_from_leading_zero_bits(8)
+        );
+        assert!(parse_difficulty_hex("00ff").is_err());
+        assert!(parse_difficulty_hex(&"zz".repeat(32)).is_err());
+    }
+
+    #[test]
+    fn solve_time_sets_expected_hashes() {
+        let target = DifficultyTarget::SolveTime {
+            seconds: 60.0,
+            hashrate: 1_000.0,
+        };
+        let hashes = expected_hashes(&target.difficulty());
+        assert!((hashes - 60_000.0).abs() / 60_000.0 < 1e-9);
+        let easiest = DifficultyTarget::SolveTime {
+            seconds: 0.0,
+            hashrate: 1_000.0,
+        };
+        assert_eq!(easiest.difficulty().to_bytes(), [255; 32]);
+    }
+
+    #[test]
+    fn rejects_unsolvable_solve_times() {
+        for value in ["0", "-60", "inf", "NaN", "soon"] {
+            assert!(parse_solve_rate(value).is_err(), "{}", value);
+        }
+        assert_eq!(parse_solve_rate("1.5"), Ok(1.5));
+        let mut hardest = [0u8; 32];
+        hardest[31] = 1;
+        let overflowing = DifficultyTarget::SolveTime {
+            seconds: 1e300,
+            hashrate: 1e300,
+        };
+        assert_eq!(overflowing.difficulty().to_bytes(), hardest);
+    }
+
+    #[tokio::test]
+    async fn refuses_signers_other_than_the_admin() {
+        let client = Arc::new(MockChainClient::new());
+        let miner = test_miner(&client);
+        set_treasury(&client, Pubkey::new_unique());
+        miner
+            .update_difficulty(DifficultyTarget::LeadingZeroBits(12), None, true)
+            .await;
+        assert!(client.sent_transactions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn sends_the_new_difficulty_as_the_admin() {
+        let (client, miner) = MockChainClient::funded();
+        set_treasury(&client, miner.signer().pubkey());
+        miner
+            .update_difficulty(DifficultyTarget::LeadingZeroBits(12), None, true)
+            .await;
+        let sent = client.sent_transactions();
+        assert_eq!(sent.len(), 1);
+        let ix = sent[0].message.instructions.last().unwrap();
+        assert_eq!(sent[0].message.account_keys[ix.program_id_index as usize], ore::ID);
     }
 }
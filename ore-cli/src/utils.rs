@@ -1,42 +1,12 @@
+use std::io::{stdin, stdout, Write};
+
 use cached::proc_macro::cached;
-use ore::{
-    self,
-    state::{Proof, Treasury},
-    utils::AccountDeserialize,
-    MINT_ADDRESS, PROOF, TREASURY_ADDRESS,
-};
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_program::{pubkey::Pubkey, sysvar};
-use solana_sdk::{clock::Clock, commitment_config::CommitmentConfig};
+use ore::{self, utils::AccountDeserialize, MINT_ADDRESS, PROOF, TOKEN_DECIMALS, TREASURY_ADDRESS};
+use solana_program::pubkey::Pubkey;
 use spl_associated_token_account::get_associated_token_address;
 
-pub async fn get_treasury(cluster: String) -> Treasury {
-    let client = RpcClient::new_with_commitment(cluster, CommitmentConfig::confirmed());
-    let data = client
-        .get_account_data(&TREASURY_ADDRESS)
-        .await
-        .expect("Failed to get treasury account");
-    *Treasury::try_from_bytes(&data).expect("Failed to parse treasury account")
-}
-
-pub async fn get_proof(cluster: String, authority: Pubkey) -> Proof {
-    let client = RpcClient::new_with_commitment(cluster, CommitmentConfig::confirmed());
-    let proof_address = proof_pubkey(authority);
-    let data = client
-        .get_account_data(&proof_address)
-        .await
-        .expect("Failed to get miner account");
-    *Proof::try_from_bytes(&data).expect("Failed to parse miner account")
-}
-
-pub async fn get_clock_account(cluster: String) -> Clock {
-    let client = RpcClient::new_with_commitment(cluster, CommitmentConfig::confirmed());
-    let data = client
-        .get_account_data(&sysvar::clock::ID)
-        .await
-        .expect("Failed to get miner account");
-    bincode::deserialize::<Clock>(&data).expect("Failed to deserialize clock")
-}
+// Decimal places of SOL, as lamports.
+const SOL_DECIMALS: u8 = 9;
 
 #[cached]
 pub fn proof_pubkey(authority: Pubkey) -> Pubkey {
@@ -47,3 +17,91 @@ pub fn proof_pubkey(authority: Pubkey) -> Pubkey {
 pub fn treasury_tokens_pubkey() -> Pubkey {
     get_associated_token_address(&TREASURY_ADDRESS, &MINT_ADDRESS)
 }
+
+/// Reads the state of an ORE account, or `None` if `data` does not hold one. Unlike
+/// `try_from_bytes`, it does not panic on data shorter than the discriminator.
+pub fn deserialize_account<T: AccountDeserialize>(data: &[u8]) -> Option<&T> {
+    if data.len() < 8 {
+        return None;
+    }
+    T::try_from_bytes(data).ok()
+}
+
+/// Parses a decimal ORE amount into base units without a floating point round trip.
+pub fn parse_ore_amount(amount: &str) -> Result<u64, String> {
+    parse_amount(amount, TOKEN_DECIMALS, "ORE")
+}
+
+/// Parses a decimal SOL amount into lamports without a floating point round trip.
+pub fn parse_sol_amount(amount: &str) -> Result<u64, String> {
+    parse_amount(amount, SOL_DECIMALS, "SOL")
+}
+
+// Only plain decimals are accepted, so signs, exponents, NaN and infinity are errors.
+fn parse_amount(amount: &str, decimals: u8, unit: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid {} amount: {:?}", unit, amount);
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole.chars().all(|c| c.is_ascii_digit())
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    if fraction.len() > decimals as usize {
+        return Err(format!(
+            "{} has more than {} decimal places",
+            amount, decimals
+        ));
+    }
+    let whole: u64 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().map_err(|_| invalid())?
+    };
+    let fraction: u64 = format!("{:0<width$}", fraction, width = decimals as usize)
+        .parse()
+        .map_err(|_| invalid())?;
+    whole
+        .checked_mul(10u64.pow(decimals as u32))
+        .and_then(|whole| whole.checked_add(fraction))
+        .ok_or_else(|| format!("{} is too large", amount))
+}
+
+/// Formats base units as an exact decimal ORE amount.
+pub fn format_ore_amount(amount: u64) -> String {
+    spl_token::amount_to_ui_amount_string_trimmed(amount, TOKEN_DECIMALS)
+}
+
+/// Asks a yes/no question on the terminal. Anything but "y" or "yes" is a no.
+pub fn confirm(prompt: &str) -> bool {
+    print!("{}", prompt);
+    stdout().flush().ok();
+    let mut answer = String::new();
+    if stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_exact_amounts() {
+        assert_eq!(parse_ore_amount("1"), Ok(1_000_000_000));
+        assert_eq!(parse_ore_amount("0.000000001"), Ok(1));
+        assert_eq!(parse_ore_amount(".5"), Ok(500_000_000));
+        assert_eq!(parse_ore_amount("18446744073.709551615"), Ok(u64::MAX));
+        assert_eq!(parse_sol_amount("0.05"), Ok(50_000_000));
+        assert_eq!(format_ore_amount(1_500_000_000), "1.5");
+    }
+
+    #[test]
+    fn rejects_invalid_amounts() {
+        for amount in ["", ".", "-1", "+1", "1e3", "NaN", "inf", "1.0000000001", "18446744074"] {
+            assert!(parse_sol_amount(amount).is_err(), "{}", amount);
+            assert!(parse_ore_amount(amount).is_err(), "{}", amount);
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use futures::{stream::select_all, Stream, StreamExt};
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::{
+    client_error::Result as ClientResult,
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::RpcAccountInfoConfig,
+    rpc_response::Response as RpcResponse,
+};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{account::Account, commitment_config::CommitmentConfig};
+
+use crate::{
+    chain_client::ChainClient,
+    pubsub::{self, PubsubConnection},
+    snapshot::{snapshot_addresses, ChainSnapshot},
+    Miner,
+};
+
+// The clock sysvar is watched too and changes every slot, so a quiet subscription is a dead one.
+const STALE_AFTER: Duration = Duration::from_secs(10);
+
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+struct WatchedAccounts {
+    live: bool,
+    updated_at: Instant,
+    slot: u64,
+    slots: Vec<u64>,
+    accounts: Vec<Option<Account>>,
+}
+
+/// An in-memory view of the accounts of a `ChainSnapshot`, kept current over
+/// `accountSubscribe` websocket subscriptions by a background task.
+pub struct AccountWatcher {
+    state: Arc<RwLock<WatchedAccounts>>,
+}
+
+impl AccountWatcher {
+    pub fn spawn(
+        pubsub: Arc<PubsubConnection>,
+        client: Arc<dyn ChainClient>,
+        commitment: CommitmentConfig,
+        authority: Pubkey,
+    ) -> Self {
+        let addresses = snapshot_addresses(Some(authority));
+        let state = Arc::new(RwLock::new(WatchedAccounts {
+            live: false,
+            updated_at: Instant::now(),
+            slot: 0,
+            slots: vec![0; addresses.len()],
+            accounts: vec![None; addresses.len()],
+        }));
+        tokio::spawn(watch(pubsub, client, commitment, addresses, state.clone()));
+        Self { state }
+    }
+
+    /// Returns the watched state, or `None` while the subscriptions are down or stale.
+    pub fn snapshot(&self) -> Option<ChainSnapshot> {
+        let state = self.state.read().unwrap();
+        if !state.live || state.updated_at.elapsed() > STALE_AFTER {
+            return None;
+        }
+        ChainSnapshot::from_accounts(state.slot, &state.accounts, true).ok()
+    }
+}
+
+async fn watch(
+    pubsub: Arc<PubsubConnection>,
+    client: Arc<dyn ChainClient>,
+    commitment: CommitmentConfig,
+    addresses: Vec<Pubkey>,
+    state: Arc<RwLock<WatchedAccounts>>,
+) {
+    let mut delay = MIN_RECONNECT_DELAY;
+    loop {
+        match subscribe(&pubsub, client.as_ref(), commitment, &addresses, &state, &mut delay).await {
+            Ok(()) => println!("Websocket subscriptions closed, polling until reconnected"),
+            Err(err) => println!("Websocket error: {}, polling until reconnected", err),
+        }
+        state.write().unwrap().live = false;
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+async fn subscribe(
+    pubsub: &PubsubConnection,
+    rpc_client: &dyn ChainClient,
+    commitment: CommitmentConfig,
+    addresses: &[Pubkey],
+    state: &RwLock<WatchedAccounts>,
+    delay: &mut Duration,
+) -> Result<(), String> {
+    let client = pubsub
+        .get()
+        .await
+        .ok_or_else(|| "not connected".to_string())?;
+    let result = watch_accounts(&client, rpc_client, commitment, addresses, state, delay).await;
+    // Whatever ended the subscriptions may have broken the connection too.
+    pubsub.reset(&client);
+    result
+}
+
+async fn watch_accounts(
+    client: &PubsubClient,
+    rpc_client: &dyn ChainClient,
+    commitment: CommitmentConfig,
+    addresses: &[Pubkey],
+    state: &RwLock<WatchedAccounts>,
+    delay: &mut Duration,
+) -> Result<(), String> {
+    let config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(commitment),
+        ..Default::default()
+    };
+    let mut streams = vec![];
+    let mut unsubscribes = vec![];
+    let mut result = Ok(());
+    for (i, address) in addresses.iter().enumerate() {
+        match client.account_subscribe(address, Some(config.clone())).await {
+            Ok((stream, unsubscribe)) => {
+                streams.push(stream.map(move |response| (i, response)));
+                unsubscribes.push(unsubscribe);
+            }
+            Err(err) => {
+                result = Err(err.to_string());
+                break;
+            }
+        }
+    }
+    if result.is_ok() {
+        result = follow_accounts(streams, rpc_client, addresses, state, delay).await;
+    }
+    for unsubscribe in unsubscribes {
+        pubsub::unsubscribe(unsubscribe);
+    }
+    result
+}
+
+async fn follow_accounts(
+    streams: Vec<impl Stream<Item = (usize, RpcResponse<UiAccount>)> + Unpin>,
+    rpc_client: &dyn ChainClient,
+    addresses: &[Pubkey],
+    state: &RwLock<WatchedAccounts>,
+    delay: &mut Duration,
+) -> Result<(), String> {
+    // Seed the view once subscribed, so no update in between is missed.
+    let response = rpc_client
+        .get_multiple_accounts(addresses)
+        .await
+        .map_err(|err| err.to_string())?;
+    {
+        let mut state = state.write().unwrap();
+        state.slot = response.context.slot;
+        state.slots = vec![response.context.slot; addresses.len()];
+        state.accounts = response.value;
+        state.updated_at = Instant::now();
+        state.live = true;
+    }
+    *delay = MIN_RECONNECT_DELAY;
+
+    let mut updates = select_all(streams);
+    while let Some((i, response)) = updates.next().await {
+        let mut state = state.write().unwrap();
+        let slot = response.context.slot;
+        if slot < state.slots[i] {
+            continue;
+        }
+        state.accounts[i] = response
+            .value
+            .decode::<Account>()
+            .filter(|account| account.lamports > 0);
+        state.slots[i] = slot;
+        state.slot = state.slot.max(slot);
+        state.updated_at = Instant::now();
+    }
+    Ok(())
+}
+
+impl Miner {
+    /// Reads the mining state from the watcher when it is live, and over RPC otherwise.
+    /// A watched proof still holding `stale_hash` has not caught up with the last landed
+    /// hash yet, so RPC is used for it as well.
+    pub async fn get_watched_snapshot(
+        &self,
+        watcher: Option<&AccountWatcher>,
+        authority: Pubkey,
+        stale_hash: Option<ore::state::Hash>,
+    ) -> ClientResult<ChainSnapshot> {
+        if let Some(snapshot) = watcher.and_then(|watcher| watcher.snapshot()) {
+            let stale = stale_hash.is_some()
+                && snapshot.proof.map(|proof| proof.hash) == stale_hash;
+            if !stale {
+                return Ok(snapshot);
+            }
+        }
+        self.get_snapshot(Some(authority)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::stream;
+    use ore::BUS_COUNT;
+    use solana_client::rpc_response::RpcResponseContext;
+
+    use super::*;
+    use crate::mock_client::{test_proof, MockChainClient};
+
+    const PROOF: usize = 3 + BUS_COUNT;
+    const TOKENS: usize = PROOF + 1;
+
+    /// Returns the snapshot accounts of the miner's authority as they are with
+    /// `claimable_rewards` in its proof, leaving the client as it was.
+    async fn accounts_with_rewards(
+        client: &MockChainClient,
+        authority: Pubkey,
+        claimable_rewards: u64,
+    ) -> Vec<Option<Account>> {
+        let addresses = snapshot_addresses(Some(authority));
+        let current = client
+            .get_multiple_accounts(&addresses)
+            .await
+            .unwrap()
+            .value;
+        client.set_proof(&test_proof(authority, claimable_rewards));
+        let accounts = client
+            .get_multiple_accounts(&addresses)
+            .await
+            .unwrap()
+            .value;
+        client.set_account(addresses[PROOF], current[PROOF].clone().unwrap());
+        accounts
+    }
+
+    fn update(i: usize, slot: u64, account: &Account) -> (usize, RpcResponse<UiAccount>) {
+        let value = UiAccount::encode(
+            &Pubkey::default(),
+            account,
+            UiAccountEncoding::Base64,
+            None,
+            None,
+        );
+        let context = RpcResponseContext {
+            slot,
+            api_version: None,
+        };
+        (i, RpcResponse { context, value })
+    }
+
+    fn watched(accounts: Vec<Option<Account>>, live: bool) -> AccountWatcher {
+        let state = WatchedAccounts {
+            live,
+            updated_at: Instant::now(),
+            slot: 1,
+            slots: vec![1; accounts.len()],
+            accounts,
+        };
+        AccountWatcher {
+            state: Arc::new(RwLock::new(state)),
+        }
+    }
+
+    #[tokio::test]
+    async fn applies_updates_newer_than_the_watched_state() {
+        let (client, miner) = MockChainClient::mining(10);
+        let authority = miner.signer().pubkey();
+        client.set_token_balance(authority, 1_000);
+        let addresses = snapshot_addresses(Some(authority));
+        let updated = accounts_with_rewards(&client, authority, 5).await;
+        let stale = accounts_with_rewards(&client, authority, 9).await;
+        let closed = Account {
+            lamports: 0,
+            ..updated[TOKENS].clone().unwrap()
+        };
+        let updates = stream::iter(vec![
+            update(PROOF, 5, updated[PROOF].as_ref().unwrap()),
+            update(PROOF, 3, stale[PROOF].as_ref().unwrap()),
+            update(TOKENS, 6, &closed),
+        ]);
+        let watcher = watched(vec![None; addresses.len()], false);
+        let mut delay = MAX_RECONNECT_DELAY;
+
+        follow_accounts(
+            vec![updates],
+            client.as_ref(),
+            &addresses,
+            &watcher.state,
+            &mut delay,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(delay, MIN_RECONNECT_DELAY);
+        let snapshot = watcher.snapshot().unwrap();
+        assert_eq!(snapshot.slot, 6);
+        assert_eq!(snapshot.proof.unwrap().claimable_rewards, 5);
+        assert_eq!(snapshot.token_balance, None);
+        assert_eq!(snapshot.sol_balance, 1_000_000_000);
+    }
+
+    #[tokio::test]
+    async fn reads_over_rpc_unless_the_watched_state_is_current() {
+        let (client, miner) = MockChainClient::mining(10);
+        let authority = miner.signer().pubkey();
+        let accounts = accounts_with_rewards(&client, authority, 5).await;
+
+        let watcher = watched(accounts.clone(), true);
+        let snapshot = miner
+            .get_watched_snapshot(Some(&watcher), authority, None)
+            .await
+            .unwrap();
+        assert_eq!(snapshot.proof.unwrap().claimable_rewards, 5);
+
+        let stale_hash = snapshot.proof.map(|proof| proof.hash);
+        let snapshot = miner
+            .get_watched_snapshot(Some(&watcher), authority, stale_hash)
+            .await
+            .unwrap();
+        assert_eq!(snapshot.proof.unwrap().claimable_rewards, 0);
+
+        let watcher = watched(accounts, false);
+        let snapshot = miner
+            .get_watched_snapshot(Some(&watcher), authority, None)
+            .await
+            .unwrap();
+        assert_eq!(snapshot.proof.unwrap().claimable_rewards, 0);
+    }
+}
@@ -0,0 +1,192 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+use ore::TOKEN_DECIMALS;
+use ore_cli::chain_client::ChainClient;
+use solana_account_decoder::parse_token::{parse_token, TokenAccountType, UiTokenAccount};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
+    rpc_request::RpcError,
+    rpc_response::{Response as RpcResponse, RpcResponseContext, RpcSimulateTransactionResult},
+};
+use solana_program_test::{BanksClient, BanksClientError};
+use solana_sdk::{
+    account::Account,
+    commitment_config::CommitmentLevel,
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{Result as TransactionResult, Transaction},
+};
+use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+
+/// Serves the requests of a miner from a `solana-program-test` bank. Transactions are
+/// executed as they are sent, and their results kept for status requests.
+pub struct BanksChainClient {
+    banks: BanksClient,
+    statuses: Mutex<HashMap<Signature, TransactionResult<()>>>,
+}
+
+impl BanksChainClient {
+    pub fn new(banks: BanksClient) -> Self {
+        Self {
+            banks,
+            statuses: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns how many transactions were executed, rebroadcasts aside.
+    pub fn executed_transactions(&self) -> usize {
+        self.statuses.lock().unwrap().len()
+    }
+
+    // Requests take the client mutably, so each one goes through a cheap clone.
+    fn banks(&self) -> BanksClient {
+        self.banks.clone()
+    }
+
+    async fn response<T>(&self, value: T) -> ClientResult<RpcResponse<T>> {
+        let slot = self.banks().get_root_slot().await.map_err(client_error)?;
+        Ok(RpcResponse {
+            context: RpcResponseContext {
+                slot,
+                api_version: None,
+            },
+            value,
+        })
+    }
+}
+
+fn client_error(err: BanksClientError) -> ClientError {
+    ClientError {
+        request: None,
+        kind: ClientErrorKind::Custom(err.to_string()),
+    }
+}
+
+#[async_trait]
+impl ChainClient for BanksChainClient {
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account> {
+        self.banks()
+            .get_account(*pubkey)
+            .await
+            .map_err(client_error)?
+            .ok_or_else(|| ClientError {
+                request: None,
+                kind: ClientErrorKind::RpcError(RpcError::ForUser(format!(
+                    "AccountNotFound: pubkey={}",
+                    pubkey
+                ))),
+            })
+    }
+
+    async fn get_account_data(&self, pubkey: &Pubkey) -> ClientResult<Vec<u8>> {
+        Ok(self.get_account(pubkey).await?.data)
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> ClientResult<RpcResponse<Vec<Option<Account>>>> {
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+        for pubkey in pubkeys.iter() {
+            accounts.push(self.banks().get_account(*pubkey).await.map_err(client_error)?);
+        }
+        self.response(accounts).await
+    }
+
+    async fn get_token_account(&self, pubkey: &Pubkey) -> ClientResult<Option<UiTokenAccount>> {
+        let account = self.banks().get_account(*pubkey).await.map_err(client_error)?;
+        Ok(account.and_then(|account| {
+            match parse_token(&account.data, Some(TOKEN_DECIMALS)) {
+                Ok(TokenAccountType::Account(token_account)) => Some(token_account),
+                _ => None,
+            }
+        }))
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> ClientResult<u64> {
+        self.banks().get_balance(*pubkey).await.map_err(client_error)
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<(Hash, u64)> {
+        self.banks()
+            .get_latest_blockhash_with_commitment(CommitmentLevel::Confirmed)
+            .await
+            .map_err(client_error)?
+            .ok_or_else(|| client_error(BanksClientError::ClientError("blockhash not found")))
+    }
+
+    async fn get_block_height(&self) -> ClientResult<u64> {
+        self.banks().get_root_block_height().await.map_err(client_error)
+    }
+
+    async fn send_transaction(
+        &self,
+        transaction: &Transaction,
+        _config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature> {
+        let signature = transaction.signatures[0];
+        let result = self
+            .banks()
+            .process_transaction_with_metadata(transaction.clone())
+            .await
+            .map_err(client_error)?;
+
+        // Only executed transactions land. Rebroadcasts are rejected without metadata.
+        if result.metadata.is_some() {
+            self.statuses.lock().unwrap().insert(signature, result.result);
+        }
+        Ok(signature)
+    }
+
+    async fn simulate_transaction(
+        &self,
+        transaction: &Transaction,
+        _config: RpcSimulateTransactionConfig,
+    ) -> ClientResult<RpcResponse<RpcSimulateTransactionResult>> {
+        let simulation = self
+            .banks()
+            .simulate_transaction(transaction.clone())
+            .await
+            .map_err(client_error)?;
+        let details = simulation.simulation_details;
+        let result = RpcSimulateTransactionResult {
+            err: simulation.result.and_then(|result| result.err()),
+            logs: details.as_ref().map(|details| details.logs.clone()),
+            accounts: None,
+            units_consumed: details.as_ref().map(|details| details.units_consumed),
+            return_data: None,
+            inner_instructions: None,
+        };
+        self.response(result).await
+    }
+
+    async fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> ClientResult<RpcResponse<Vec<Option<TransactionStatus>>>> {
+        let slot = self.banks().get_root_slot().await.map_err(client_error)?;
+        let statuses = self.statuses.lock().unwrap();
+        let value = signatures
+            .iter()
+            .map(|signature| {
+                statuses.get(signature).map(|status| TransactionStatus {
+                    slot,
+                    confirmations: None,
+                    status: status.clone(),
+                    err: status.clone().err(),
+                    confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+                })
+            })
+            .collect();
+        Ok(RpcResponse {
+            context: RpcResponseContext {
+                slot,
+                api_version: None,
+            },
+            value,
+        })
+    }
+}
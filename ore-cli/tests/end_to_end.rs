@@ -0,0 +1,215 @@
+//! Runs the commands of the CLI against the ORE program in a `solana-program-test` bank:
+//! initializes the program, lowers the difficulty, then mines, claims and sends ORE, and
+//! bootstraps a local environment. Needs the `admin` feature for the admin commands.
+
+mod common;
+
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use common::BanksChainClient;
+use ore::{
+    state::{Proof, Treasury},
+    utils::AccountDeserialize,
+    MINT_ADDRESS, TREASURY_ADDRESS,
+};
+use ore_cli::{
+    chain_client::ChainClient,
+    keys::{keypair_paths, read_pubkey},
+    update_difficulty::DifficultyTarget,
+    utils::proof_pubkey,
+    MineConfig, Miner,
+};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    native_token::LAMPORTS_PER_SOL,
+    program_pack::Pack,
+    pubkey,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+};
+use spl_associated_token_account::get_associated_token_address;
+
+// The token metadata program, which `initialize` calls to describe the mint.
+const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+// How long mining may take to land a hash at the lowered difficulty.
+const MINE_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Metadata is not under test, so the program accepts any call without creating it.
+fn accept_metadata(_: &Pubkey, _: &[AccountInfo], _: &[u8]) -> ProgramResult {
+    Ok(())
+}
+
+fn wallet(lamports: u64) -> Account {
+    Account {
+        lamports,
+        owner: system_program::ID,
+        ..Default::default()
+    }
+}
+
+// Builds a miner served by the bank, confirming by polling without delay.
+fn miner(client: &Arc<BanksChainClient>, signer: &Keypair) -> Miner {
+    Miner::builder("http://localhost:8899")
+        .client(client.clone())
+        .websocket_url("")
+        .signer(Box::new(Keypair::from_bytes(&signer.to_bytes()).unwrap()))
+        .confirm_interval(0)
+        .build()
+}
+
+async fn token_balance(client: &BanksChainClient, owner: &Pubkey) -> u64 {
+    let address = get_associated_token_address(owner, &MINT_ADDRESS);
+    let data = client.get_account_data(&address).await.unwrap();
+    spl_token::state::Account::unpack(&data).unwrap().amount
+}
+
+async fn proof(client: &BanksChainClient, authority: &Pubkey) -> Proof {
+    let data = client.get_account_data(&proof_pubkey(*authority)).await.unwrap();
+    *Proof::try_from_bytes(&data).unwrap()
+}
+
+async fn treasury(client: &BanksChainClient) -> Treasury {
+    let data = client.get_account_data(&TREASURY_ADDRESS).await.unwrap();
+    *Treasury::try_from_bytes(&data).unwrap()
+}
+
+// Starts a bank with the program loaded and `wallets` holding 10 SOL each. The context
+// has to outlive the client.
+async fn start(wallets: &[&Keypair]) -> (ProgramTestContext, Arc<BanksChainClient>) {
+    let mut program_test = ProgramTest::new("ore", ore::ID, processor!(ore::process_instruction));
+    program_test.add_program(
+        "mpl_token_metadata",
+        METADATA_PROGRAM_ID,
+        processor!(accept_metadata),
+    );
+    for keypair in wallets.iter() {
+        program_test.add_account(keypair.pubkey(), wallet(10 * LAMPORTS_PER_SOL));
+    }
+    let context = program_test.start_with_context().await;
+
+    // Mining is only open past the program's start time.
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    let clock = context.banks_client.clone().get_sysvar::<Clock>().await.unwrap();
+    context.set_sysvar(&Clock {
+        unix_timestamp: now,
+        ..clock
+    });
+    let client = Arc::new(BanksChainClient::new(context.banks_client.clone()));
+    (context, client)
+}
+
+#[tokio::test]
+async fn initialize_mine_claim_and_send() {
+    let admin = Keypair::new();
+    let signer = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    let (_context, client) = start(&[&admin, &signer]).await;
+
+    // Initialize the program and lower the difficulty.
+    let admin_miner = miner(&client, &admin);
+    admin_miner.initialize().await;
+    assert_eq!(treasury(&client).await.admin, admin.pubkey());
+    admin_miner
+        .update_difficulty(DifficultyTarget::LeadingZeroBits(8), None, true)
+        .await;
+    let difficulty = treasury(&client).await.difficulty;
+    assert_eq!(difficulty.0[0], 0);
+    assert_eq!(difficulty.0[1..], [255; 31]);
+
+    // Mine until a hash lands. The first transaction also resets the overdue epoch.
+    let miner = miner(&client, &signer);
+
+    let landed = async {
+        loop {
+            if let Ok(data) = client.get_account_data(&proof_pubkey(signer.pubkey())).await {
+                if Proof::try_from_bytes(&data).unwrap().total_hashes > 0 {
+                    break;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    };
+    let mining = miner.mine(MineConfig::default());
+    tokio::select! {
+        _ = mining => panic!("Mining stopped"),
+        _ = landed => {}
+        _ = tokio::time::sleep(MINE_TIMEOUT) => panic!("No hash landed"),
+    }
+    let reward_rate = treasury(&client).await.reward_rate;
+    let rewards = proof(&client, &signer.pubkey()).await.claimable_rewards;
+    assert!(rewards > 0);
+    assert_eq!(rewards % reward_rate, 0);
+
+    // Claim everything into the signer's token account, created on the way.
+    assert_eq!(miner.claim(None, None, None).await, 0);
+    assert_eq!(token_balance(&client, &signer.pubkey()).await, rewards);
+    assert_eq!(proof(&client, &signer.pubkey()).await.claimable_rewards, 0);
+
+    // Send part of it on, creating the recipient's token account.
+    let sent = rewards / 4;
+    let code = miner
+        .send_ore(recipient.to_string(), Some(sent), Some("end to end".to_string()), true)
+        .await;
+    assert_eq!(code, 0);
+    assert_eq!(token_balance(&client, &recipient).await, sent);
+    assert_eq!(token_balance(&client, &signer.pubkey()).await, rewards - sent);
+}
+
+#[tokio::test]
+async fn localnet_bootstrap() {
+    let admin = Keypair::new();
+    let (_context, client) = start(&[&admin]).await;
+    let dir = std::env::temp_dir().join(format!("ore-localnet-{}", Pubkey::new_unique()));
+    let keys_dir = dir.join("keys").to_string_lossy().to_string();
+    let config_path = dir.join("orchestrator.json").to_string_lossy().to_string();
+    let admin_miner = miner(&client, &admin);
+    let bootstrap = || {
+        admin_miner.dev_localnet(
+            "http://127.0.0.1:8899".to_string(),
+            2,
+            keys_dir.clone(),
+            LAMPORTS_PER_SOL,
+            "./bin/ore".to_string(),
+            Some(config_path.clone()),
+        )
+    };
+    bootstrap().await;
+
+    let treasury = treasury(&client).await;
+    assert_eq!(treasury.admin, admin.pubkey());
+    assert_eq!(treasury.difficulty.0[0], 0);
+    assert_eq!(treasury.difficulty.0[1..], [255; 31]);
+    let paths = keypair_paths(&keys_dir).unwrap();
+    assert_eq!(paths.len(), 2);
+    let mut pubkeys = vec![];
+    for path in paths.iter() {
+        let pubkey = read_pubkey(path).unwrap();
+        assert_eq!(client.get_balance(&pubkey).await.unwrap(), LAMPORTS_PER_SOL);
+        pubkeys.push(pubkey);
+    }
+    let config: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+    assert_eq!(config["keypairs_dir"], keys_dir.as_str());
+    assert_eq!(config["rpcs"][0], "http://127.0.0.1:8899");
+
+    // Running it again keeps the keypairs and sends nothing new.
+    let executed = client.executed_transactions();
+    let admin_balance = client.get_balance(&admin.pubkey()).await.unwrap();
+    bootstrap().await;
+    assert_eq!(client.executed_transactions(), executed);
+    assert_eq!(client.get_balance(&admin.pubkey()).await.unwrap(), admin_balance);
+    let paths = keypair_paths(&keys_dir).unwrap();
+    let rerun_pubkeys: Vec<Pubkey> =
+        paths.iter().map(|path| read_pubkey(path).unwrap()).collect();
+    assert_eq!(rerun_pubkeys, pubkeys);
+    std::fs::remove_dir_all(&dir).unwrap();
+}